@@ -2,7 +2,16 @@ import numpy
 from ndarray_threaded_window.ndarray_threaded_window import *
 
 
-def apply_window(array: numpy.ndarray, method: int, window_shape: list[int]) -> numpy.ndarray:
+CONVERSION_PRESETS = {
+    "default": ("half_away", "saturate"),
+    "numpy": ("truncate", "error"),
+}
+
+
+def apply_window(array: numpy.ndarray, method: int, window_shape: list[int], nodata=None,
+                 valid_mask: numpy.ndarray | None = None, min_valid: int = 1, return_count: bool = False,
+                 conversion: str = "default", rounding: str | None = None, overflow: str | None = None,
+                 accumulation: str = "fallback"):
     """
     method can be chosen by using the included options or from the list below with `int`
         0: "func_window_max"
@@ -14,24 +23,665 @@ def apply_window(array: numpy.ndarray, method: int, window_shape: list[int]) ->
         6: "func_fast_std_clamp"
         7: "func_fast_population_std"
         8: "func_fast_sample_std"
+        9: "func_michelson_contrast"
+        10: "func_exact_population_std"
 
-    :param array: input ndarray
+    float arrays are summed in float64 and the result is returned in the input dtype,
+    func_michelson_contrast is not scaled or offset for float arrays, signed integers are offset by the dtype minimum
+
+    results are written into integer dtypes with the `conversion` preset, "default" rounds half away
+    from zero and saturates, "numpy" truncates and raises OverflowError for values the dtype can't hold,
+    with func_stdev_ddof_0 it gives the same values as `int(np.std(window))`,
+    `rounding` and `overflow` replace the part of the preset they name
+
+    integer windows are summed exactly in a wider integer, with `accumulation="fallback"` a window
+    sum which overflows is summed again in float64, with "checked" an OverflowError is raised instead,
+    sums are only checked for methods which add up the window (not func_window_max, func_window_min,
+    func_stdev_ddof_0, func_stdev_ddof_1 or func_michelson_contrast) and when the window is large enough
+    to overflow
+
+    elements equal to `nodata` (any NaN when `nodata` is NaN) and elements where `valid_mask` is False
+    are left out of every window, windows with fewer than `min_valid` valid elements are set to
+    `nodata` (0 for integer or NaN for float arrays when only `valid_mask` is given)
+
+    :param array: input ndarray, integer or float
     :param method: see method notes above
     :param window_shape: size of the window; expects a list of int values the same length as [array.shape]
-    :return: numpy array with the same number of dimensions as the input
-    """
-    match str(array.dtype):
-        case "uint8":
-            return _nd_thread_window_subspace.apply_window_for_dyn_u8(array, method, window_shape)
-        case "uint16":
-            return _nd_thread_window_subspace.apply_window_for_dyn_u16(array, method, window_shape)
-        case "uint32":
-            return _nd_thread_window_subspace.apply_window_for_dyn_u32(array, method, window_shape)
-        case "int8":
-            return _nd_thread_window_subspace.apply_window_for_dyn_i8(array, method, window_shape)
-        case "int16":
-            return _nd_thread_window_subspace.apply_window_for_dyn_i16(array, method, window_shape)
-        case "int32":
-            return _nd_thread_window_subspace.apply_window_for_dyn_i32(array, method, window_shape)
+    :param nodata: value marking missing elements
+    :param valid_mask: bool ndarray with the same shape as `array`, True for valid elements
+    :param min_valid: smallest number of valid elements in a window
+    :param return_count: also return the number of valid elements in each window
+    :param conversion: one of "default", "numpy"
+    :param rounding: one of "half_even", "half_away", "floor", "ceil", "truncate"
+    :param overflow: one of "saturate", "error"
+    :param accumulation: one of "fallback", "checked"
+    :return: numpy array with the same number of dimensions as the input,
+        or (output, uint64 valid counts) when `return_count`
+    """
+    rounding, overflow = _conversion_policy(conversion, rounding, overflow)
+    if accumulation not in ("fallback", "checked"):
+        raise ValueError(f"unknown accumulation {accumulation!r}")
+    checked = accumulation == "checked"
+    if nodata is None and valid_mask is None and not return_count:
+        if (rounding, overflow) == CONVERSION_PRESETS["default"] and not checked:
+            return _dtype_function("apply_window", array)(array, method, window_shape)
+        return _dtype_function("apply_window_converted", array)(array, method, window_shape, rounding, overflow,
+                                                                checked)
+    fill = nodata
+    if fill is None:
+        fill = numpy.nan if numpy.issubdtype(array.dtype, numpy.floating) else 0
+    if valid_mask is not None:
+        valid_mask = numpy.asarray(valid_mask, dtype=bool)
+    out, count = _dtype_function("apply_window_masked", array)(array, method, window_shape, valid_mask, nodata,
+                                                               fill, min_valid, rounding, overflow,
+                                                               checked)
+    return (out, count) if return_count else out
+
+
+def _conversion_policy(conversion: str, rounding: str | None, overflow: str | None) -> tuple[str, str]:
+    """
+    the (rounding, overflow) of the `conversion` preset with the parts named by `rounding` and `overflow` replaced
+    """
+    if conversion not in CONVERSION_PRESETS:
+        raise ValueError(f"unknown conversion preset {conversion!r}")
+    preset_rounding, preset_overflow = CONVERSION_PRESETS[conversion]
+    return (preset_rounding if rounding is None else rounding, preset_overflow if overflow is None else overflow)
+
+
+def _dtype_function(name: str, array: numpy.ndarray):
+    """
+    find the compiled version of `name` for the dtype of `array`
+    """
+    suffix = {
+        "uint8": "u8",
+        "uint16": "u16",
+        "uint32": "u32",
+        "int8": "i8",
+        "int16": "i16",
+        "uint64": "u64",
+        "int32": "i32",
+        "int64": "i64",
+        "float32": "f32",
+        "float64": "f64",
+    }.get(str(array.dtype))
+    function = getattr(_nd_thread_window_subspace, f"{name}_for_dyn_{suffix}", None)
+    if function is None:
+        raise NotImplementedError(f"Arrays of dtype {array.dtype} are not supported by {name}")
+    return function
+
+
+def grey_morphology(array: numpy.ndarray, footprint: numpy.ndarray | list[int], operation: str = "erosion",
+                    mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    grey-scale erosion, dilation, opening, closing, morphological gradient or top-hat,
+    the same as the `scipy.ndimage` functions of the same name
+
+    :param array: input ndarray
+    :param footprint: bool ndarray structuring element, or a window shape for a rectangle
+    :param operation: one of "erosion", "dilation", "opening", "closing", "gradient", "white_tophat",
+        "black_tophat"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: numpy array of the input dtype, same shape as the input unless mode is "valid"
+    """
+    footprint = _bool_footprint(footprint)
+    return _dtype_function("grey_morphology", array)(array, footprint, operation, mode, cval)
+
+
+def hit_or_miss(array: numpy.ndarray, hit: numpy.ndarray, miss: numpy.ndarray | None = None,
+                mode: str = "constant", cval: float = 0.0) -> numpy.ndarray:
+    """
+    unconstrained grey-scale hit-or-miss transform, `min(window[hit]) - max(window[miss])` or zero
+    when that is negative
+
+    :param array: input ndarray
+    :param hit: bool ndarray of the elements which must be foreground
+    :param miss: bool ndarray of the elements which must be background, defaults to the inverse of `hit`
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: numpy array of the input dtype, same shape as the input unless mode is "valid"
+    """
+    hit = numpy.asarray(hit, dtype=bool)
+    miss = ~hit if miss is None else numpy.asarray(miss, dtype=bool)
+    return _dtype_function("hit_or_miss", array)(array, hit, miss, mode, cval)
+
+
+def _bool_footprint(footprint: numpy.ndarray | list[int]) -> numpy.ndarray:
+    """
+    a bool footprint, a list of window lengths gives a rectangle
+    """
+    if isinstance(footprint, (list, tuple)):
+        return numpy.ones(footprint, dtype=bool)
+    return numpy.asarray(footprint) != 0
+
+
+def gaussian_filter(array: numpy.ndarray, sigma: list[float], truncate: float = 4.0,
+                    mode: str = "reflect", cval: float = 0.0, conversion: str = "default",
+                    rounding: str | None = None, overflow: str | None = None) -> numpy.ndarray:
+    """
+    separable gaussian blur, the result is written back into the input dtype with the `conversion`
+    preset, see :py:func:`apply_window`
+
+    :param array: input ndarray
+    :param sigma: standard deviation for each axis, missing axes are not blurred
+    :param truncate: kernel radius in standard deviations
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param conversion: one of "default", "numpy"
+    :param rounding: one of "half_even", "half_away", "floor", "ceil", "truncate"
+    :param overflow: one of "saturate", "error"
+    :return: numpy array with the same dtype as the input, same shape unless mode is "valid"
+    """
+    rounding, overflow = _conversion_policy(conversion, rounding, overflow)
+    return _dtype_function("gaussian_filter", array)(array, sigma, truncate, mode, cval, rounding, overflow)
+
+
+def gradient(array: numpy.ndarray, axis: int, operator: str = "sobel", mode: str = "reflect",
+             cval: float = 0.0, output: str = "float64") -> numpy.ndarray:
+    """
+    derivative along `axis`, smoothed along every other axis
+
+    :param array: input ndarray
+    :param axis: axis to differentiate
+    :param operator: one of "sobel", "prewitt", "scharr"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param output: output dtype, a float or a signed integer type such as "int16" or "int32",
+        integer outputs are rounded and saturated
+    :return: numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("gradient_axis", array)(array, axis, operator, mode, cval, output)
+
+
+def gradient_magnitude(array: numpy.ndarray, operator: str = "sobel", mode: str = "reflect",
+                       cval: float = 0.0, output: str = "float64") -> numpy.ndarray:
+    """
+    euclidean norm of :py:func:`gradient` over every axis
+
+    :param array: input ndarray
+    :param operator: one of "sobel", "prewitt", "scharr"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param output: output dtype
+    :return: numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("gradient_magnitude", array)(array, operator, mode, cval, output)
+
+
+def laplace(array: numpy.ndarray, mode: str = "reflect", cval: float = 0.0,
+            output: str = "float64") -> numpy.ndarray:
+    """
+    N dimensional discrete Laplacian, the sum of the second differences along every axis
+
+    :param array: input ndarray
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param output: output dtype, a float or a signed integer type
+    :return: numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("laplace", array)(array, mode, cval, output)
+
+
+def gaussian_laplace(array: numpy.ndarray, sigma: list[float], truncate: float = 4.0,
+                     mode: str = "reflect", cval: float = 0.0, output: str = "float64") -> numpy.ndarray:
+    """
+    Laplacian of Gaussian
+
+    :param array: input ndarray
+    :param sigma: standard deviation for each axis, missing axes are not blurred
+    :param truncate: kernel radius in standard deviations
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param output: output dtype, a float or a signed integer type
+    :return: numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("gaussian_laplace", array)(array, sigma, truncate, mode, cval, output)
+
+
+def laplacian_variance(array: numpy.ndarray, window_shape: list[int] | None = None,
+                       mode: str = "reflect", cval: float = 0.0) -> float | numpy.ndarray:
+    """
+    variance of the Laplacian, a focus measure: sharper images score higher
+
+    :param array: input ndarray
+    :param window_shape: when given return a float64 map of the variance over each window,
+        otherwise a single score for the whole array
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    """
+    if window_shape is None:
+        return _dtype_function("laplacian_variance", array)(array, mode, cval)
+    return _dtype_function("laplacian_variance_map", array)(array, window_shape, mode, cval)
+
+
+def bilateral_filter(array: numpy.ndarray, window_shape: list[int], sigma_spatial: float,
+                     sigma_range: float, mode: str = "reflect", cval: float = 0.0, conversion: str = "default",
+                     rounding: str | None = None, overflow: str | None = None) -> numpy.ndarray:
+    """
+    edge preserving smoothing, each element becomes an average of its window weighted by
+    distance from the centre and by difference from the centre value, the result is written back
+    into the input dtype with the `conversion` preset, see :py:func:`apply_window`
+
+    :param array: input ndarray
+    :param window_shape: size of the window, odd sizes keep the window centred
+    :param sigma_spatial: standard deviation of the distance weights, in elements, positive
+    :param sigma_range: standard deviation of the value difference weights, positive
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param conversion: one of "default", "numpy"
+    :param rounding: one of "half_even", "half_away", "floor", "ceil", "truncate"
+    :param overflow: one of "saturate", "error"
+    :return: numpy array with the same dtype as the input, same shape unless mode is "valid"
+    """
+    rounding, overflow = _conversion_policy(conversion, rounding, overflow)
+    return _dtype_function("bilateral_filter", array)(array, window_shape, sigma_spatial, sigma_range, mode, cval,
+                                                      rounding, overflow)
+
+
+def local_binary_pattern(array: numpy.ndarray, points: int = 8, radius: float = 1.0,
+                         method: str = "default", mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    local binary pattern labels over the first two axes of every 2d slice
+
+    :param array: input ndarray with at least 2 dimensions
+    :param points: number of neighbours on the circle, 1 to 32
+    :param radius: radius of the circle, neighbours between elements are bilinearly interpolated
+    :param method: one of "default", "ror", "uniform", "nri_uniform"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: uint32 numpy array of labels
+    """
+    return _dtype_function("local_binary_pattern", array)(array, points, radius, method, mode, cval)
+
+
+def lbp_histogram(labels: numpy.ndarray, window_shape: list[int], bins: int) -> numpy.ndarray:
+    """
+    histogram of the labels in each window, counts are added as a new last axis of length `bins`
+
+    :param labels: output of :py:func:`local_binary_pattern`
+    :param window_shape: size of the window
+    :param bins: number of labels, labels outside of `range(bins)` are ignored
+    :return: uint32 numpy array with one more dimension than `labels`
+    """
+    return _nd_thread_window_subspace.lbp_histogram(labels.astype(numpy.uint32, copy=False), window_shape, bins)
+
+
+def threshold_local(array: numpy.ndarray, window_shape: list[int], method: str = "sauvola",
+                    k: float | None = None, r: float | None = None, contrast_threshold: float = 15.0,
+                    mode: str = "reflect", cval: float = 0.0, output: str = "mask") -> numpy.ndarray:
+    """
+    adaptive local thresholding from the mean and standard deviation (or min and max) of each window
+
+    methods, with `m` and `s` the local mean and population standard deviation:
+        "niblack": m + k * s, k defaults to -0.2
+        "sauvola": m * (1 + k * (s / r - 1)), k defaults to 0.2, r to half of the dtype range
+        "wolf": (1 - k) * m + k * min + k * (s / max_s) * (m - min), k defaults to 0.5
+        "bernsen": (local_min + local_max) / 2, windows with less contrast than
+            `contrast_threshold` use the middle of the dtype range
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param method: see methods above
+    :param k: method parameter, see methods above
+    :param r: sauvola dynamic range of the standard deviation
+    :param contrast_threshold: bernsen minimum contrast
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :param output: "mask" for a bool array, true where the array is above the threshold,
+        or "threshold" for the float64 threshold of each element
+    """
+    match output:
+        case "mask":
+            name = "threshold_mask"
+        case "threshold":
+            name = "threshold_map"
         case x:
-            raise NotImplementedError(f"Arrays of dtype {x} are not supported")
+            raise ValueError(f"unknown output {x}")
+    return _dtype_function(name, array)(array, window_shape, method, k, r, contrast_threshold, mode, cval)
+
+
+def contrast(array: numpy.ndarray, window_shape: list[int], measure: str = "rms",
+             mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    local contrast of the window centred on each element
+
+    measures:
+        "rms": population standard deviation of the window, in the units of the input
+        "michelson": (max - min) / (max + min), 0 when max + min is 0, signed integers are offset
+            by the dtype minimum first, negative floats can give values outside 0 to 1
+        "weber": (centre - background) / background, the background is the mean of the window
+            without the centre element, 0 when the background is 0
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param measure: see measures above
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: float64 numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("contrast_map", array)(array, window_shape, measure, mode, cval)
+
+
+def peak_local_max(array: numpy.ndarray, min_distance: int = 1, threshold: float | None = None,
+                   strict: bool = False, plateau: str = "centre") -> numpy.ndarray:
+    """
+    coordinates of the local maxima, one row per peak, highest peaks first
+
+    an element is a peak when it is the maximum of the `2 * min_distance + 1` window centred on it,
+    peaks within `min_distance` of a higher peak are dropped
+
+    :param array: input ndarray
+    :param min_distance: smallest allowed distance (along any axis) between two peaks
+    :param threshold: only peaks greater than this are returned
+    :param strict: peaks must be greater than all of their neighbours, equal neighbours are never peaks
+    :param plateau: which elements of a flat topped peak are returned, one of "all", "first", "centre"
+    :return: integer numpy array of shape (peaks, array.ndim)
+    """
+    return _dtype_function("peak_local_max", array)(array, min_distance, threshold, strict, plateau)
+
+
+def window_extremum_offset(array: numpy.ndarray, window_shape: list[int], extremum: str = "max",
+                           per_axis: bool = False, mode: str = "reflect",
+                           cval: float = 0.0) -> tuple[numpy.ndarray, numpy.ndarray]:
+    """
+    extreme value of every window and where in the window it is, ties go to the first element
+    in row major order
+
+    the offsets are counted from the first element of the window, with any mode except "valid"
+    the window is centred so the element itself is at `window_shape // 2`
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param extremum: "max" or "min"
+    :param per_axis: give the offset along each axis on a new last axis instead of the flat
+        (row major) offset
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: the values with the input dtype, and the integer offsets
+    """
+    return _dtype_function("extremum_offset", array)(array, window_shape, extremum, per_axis, mode, cval)
+
+
+def window_moment(array: numpy.ndarray, window_shape: list[int], moment: str = "skewness", order: int = 2,
+                  mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    higher order moment of the window centred on each element, the window sum uses integer addition
+
+    moments (biased estimators, the same as scipy.stats with bias=True):
+        "central": sum((x - mean) ** order) / n
+        "skewness": m3 / m2 ** 1.5, 0 for flat windows
+        "kurtosis": excess kurtosis m4 / m2 ** 2 - 3, 0 for flat windows
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param moment: see moments above
+    :param order: order of the "central" moment
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: float64 numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("moment_map", array)(array, window_shape, moment, order, mode, cval)
+
+
+def window_robust(array: numpy.ndarray, window_shape: list[int], statistic: str = "mad", alpha: float = 0.1,
+                  scaled: bool = False, mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    robust spread or location of the window centred on each element
+
+    statistics:
+        "mad": median(|x - median(x)|), multiplied by 1.4826 when `scaled` to estimate the
+            standard deviation of normally distributed data
+        "trimmed_mean": mean without the int(alpha * n) smallest and largest values
+        "winsorised_mean": mean with the int(alpha * n) smallest and largest values replaced by
+            the nearest value which is kept
+
+    8 bit inputs use a counting sort for larger windows
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param statistic: see statistics above
+    :param alpha: proportion cut from each end, at least 0 and less than 0.5
+    :param scaled: scale the "mad" to a standard deviation
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: float64 numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("robust_map", array)(array, window_shape, statistic, alpha, scaled, mode, cval)
+
+
+def window_nan_reduce(array: numpy.ndarray, window_shape: list[int], reduction: str = "mean",
+                      nan_policy: str = "propagate", min_count: int = 1, ddof: int = 0,
+                      mode: str = "constant", cval: float = numpy.nan) -> numpy.ndarray:
+    """
+    NaN aware reduction of the window centred on each element of a float array
+
+    reductions: "max", "min", "mean", "std" (with `ddof`), "median"
+
+    nan policies:
+        "propagate": any NaN in the window gives NaN
+        "omit": NaN elements are skipped like `numpy.nanmean`, the result is NaN when fewer than
+            `min_count` valid elements are left
+
+    the default border pads with NaN, so with "omit" the edges use the part of the window inside
+    the array
+
+    :param array: input float32 or float64 ndarray
+    :param window_shape: size of the window
+    :param reduction: see reductions above
+    :param nan_policy: see nan policies above
+    :param min_count: smallest number of valid elements with "omit"
+    :param ddof: delta degrees of freedom for "std"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: numpy array with the same dtype as the input, same shape unless mode is "valid"
+    """
+    return _dtype_function("nan_reduce_map", array)(array, window_shape, reduction, ddof, nan_policy, min_count,
+                                                    mode, cval)
+
+
+def binary_morphology(array: numpy.ndarray, footprint: numpy.ndarray | list[int], operation: str = "erosion",
+                      mode: str = "constant", cval: bool = False) -> numpy.ndarray:
+    """
+    binary erosion, dilation, opening or closing of a bool array
+
+    2-D arrays are bit-packed, 64 pixels per word, instead of working on one byte per pixel
+
+    :param array: input bool ndarray, other dtypes are converted with `!= 0`
+    :param footprint: bool ndarray structuring element, or a window shape for a rectangle
+    :param operation: one of "erosion", "dilation", "opening", "closing"
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: bool numpy array, same shape as the input unless mode is "valid"
+    """
+    footprint = _bool_footprint(footprint)
+    return _nd_thread_window_subspace.binary_morphology(numpy.asarray(array) != 0, footprint, operation, mode,
+                                                        cval)
+
+
+def neighbour_count(array: numpy.ndarray, footprint: numpy.ndarray | list[int], mode: str = "constant",
+                    cval: bool = False) -> numpy.ndarray:
+    """
+    number of True elements under the footprint centred on each element of a bool array,
+    the element itself is counted when the centre of the footprint is True
+
+    :param array: input bool ndarray, other dtypes are converted with `!= 0`
+    :param footprint: bool ndarray structuring element, or a window shape for a rectangle
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: uint64 numpy array, same shape as the input unless mode is "valid"
+    """
+    footprint = _bool_footprint(footprint)
+    return _nd_thread_window_subspace.neighbour_count(numpy.asarray(array) != 0, footprint, mode, cval)
+
+
+def majority_filter(array: numpy.ndarray, window_shape: list[int], tie: str = "centre",
+                    mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    most common value (mode) in the window centred on each element, for classified rasters
+
+    8 and 16 bit inputs keep a count of every class while the window slides along the last axis
+
+    :param array: input ndarray of integer classes
+    :param window_shape: size of the window
+    :param tie: value kept when classes are equally common, one of "smallest", "largest",
+        "centre" (the centre element when it is one of the tied classes, otherwise the smallest)
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: numpy array with the same dtype as the input, same shape unless mode is "valid"
+    """
+    return _dtype_function("majority_filter", array)(array, window_shape, tie, mode, cval)
+
+
+def minority_filter(array: numpy.ndarray, window_shape: list[int], tie: str = "centre",
+                    mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    least common value present in the window centred on each element,
+    see :py:func:`majority_filter` for the parameters
+    """
+    return _dtype_function("minority_filter", array)(array, window_shape, tie, mode, cval)
+
+
+def variety_filter(array: numpy.ndarray, window_shape: list[int], mode: str = "reflect",
+                   cval: float = 0.0) -> numpy.ndarray:
+    """
+    number of distinct values in the window centred on each element
+
+    :param array: input ndarray of integer classes
+    :param window_shape: size of the window
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: integer numpy array, same shape as the input unless mode is "valid"
+    """
+    return _dtype_function("variety_filter", array)(array, window_shape, mode, cval)
+
+
+def glcm_features(array: numpy.ndarray, window_shape: list[int], offsets: list[list[int]] | None = None,
+                  levels: int = 32,
+                  features: list[str] | tuple[str, ...] = ("contrast", "homogeneity", "energy", "correlation", "entropy"),
+                  mode: str = "reflect", cval: float = 0.0) -> numpy.ndarray:
+    """
+    Haralick texture features from the grey level co-occurrence matrix (GLCM) of the window
+    centred on each element
+
+    the array is quantised into `levels` grey levels between its smallest and largest value,
+    the pairs of every offset are counted into one symmetric, normalised matrix `P`
+
+    features:
+        "contrast": sum(P[i, j] * (i - j) ** 2)
+        "homogeneity": sum(P[i, j] / (1 + (i - j) ** 2))
+        "energy": sqrt(sum(P[i, j] ** 2))
+        "correlation": sum(P[i, j] * (i - mean) * (j - mean)) / variance, 1 for flat windows
+        "entropy": -sum(P[i, j] * ln(P[i, j]))
+
+    :param array: input ndarray
+    :param window_shape: size of the window
+    :param offsets: one signed step per axis for each offset, defaults to one step along the last axis
+    :param levels: number of grey levels, between 1 and 65536
+    :param features: features to calculate, in the order of the output channels
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array when mode is "constant"
+    :return: float64 numpy array with one more dimension than the input, one channel per feature
+    """
+    if offsets is None:
+        offsets = [[0] * (array.ndim - 1) + [1]]
+    return _dtype_function("glcm_features", array)(array, window_shape, offsets, levels, list(features), mode, cval)
+
+
+def window_pair_statistic(a: numpy.ndarray, b: numpy.ndarray, window_shape: list[int],
+                          statistic: str = "correlation", mode: str = "reflect",
+                          cval: float = 0.0) -> numpy.ndarray:
+    """
+    statistic of the windows centred on each element of two arrays, the same window slides over
+    both arrays in lockstep
+
+    statistics:
+        "correlation": Pearson correlation coefficient, 0 where either window is flat
+        "covariance": population covariance
+        "weighted_mean": mean of `a` weighted by `b`, 0 where the weights sum to 0
+
+    :param a: input ndarray
+    :param b: input ndarray with the same shape and dtype as `a`
+    :param window_shape: size of the window
+    :param statistic: see statistics above
+    :param mode: border handling for both arrays, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the arrays when mode is "constant"
+    :return: float64 numpy array, same shape as the inputs unless mode is "valid"
+    """
+    if a.dtype != b.dtype:
+        raise ValueError(f"both arrays must have the same dtype, got {a.dtype} and {b.dtype}")
+    return _dtype_function("pair_map", a)(a, b, window_shape, statistic, mode, cval)
+
+
+def match_template(image: numpy.ndarray, template: numpy.ndarray, method: str = "ncc") -> numpy.ndarray:
+    """
+    score the template against every window of the image with the same shape as the template
+
+    methods:
+        "ncc": zero mean normalised cross correlation between -1 and 1, higher is better,
+            0 where the window or the template is flat
+        "ssd": sum of squared differences, lower is better
+        "sad": sum of absolute differences, lower is better
+
+    window sums for "ncc" and "ssd" come from integral images
+
+    :param image: input ndarray
+    :param template: ndarray with the same dtype and number of dimensions as `image`
+    :param method: see methods above
+    :return: float64 numpy array with the shape `image.shape - template.shape + 1`
+    """
+    if image.dtype != template.dtype:
+        raise ValueError(f"image and template must have the same dtype, got {image.dtype} and {template.dtype}")
+    return _dtype_function("match_template", image)(image, template, method)
+
+
+def best_match_locations(scores: numpy.ndarray, method: str = "ncc", count: int = 1,
+                         min_distance: int = 0) -> numpy.ndarray:
+    """
+    positions of the best scores from :py:func:`match_template`, best first
+
+    :param scores: output of :py:func:`match_template`
+    :param method: the method used for the scores, decides whether higher or lower is better
+    :param count: maximum number of locations
+    :param min_distance: locations within this distance (along any axis) of a better one are skipped
+    :return: integer numpy array of shape (locations, scores.ndim), the top left corner of each match
+    """
+    return _nd_thread_window_subspace.best_matches(scores.astype(numpy.float64, copy=False), method, count,
+                                                   min_distance)
+
+
+def ssim(a: numpy.ndarray, b: numpy.ndarray, window: str = "gaussian", sigma: float = 1.5, size: int = 7,
+         k1: float = 0.01, k2: float = 0.03, data_range: float | None = None, mode: str = "valid",
+         cval: float = 0.0, full: bool = False):
+    """
+    structural similarity (SSIM) of two arrays with the same shape and dtype
+
+    the local means, variances and covariance are population estimates, with mode "valid" the mean
+    is the same as `skimage.metrics.structural_similarity(..., use_sample_covariance=False)`
+
+    :param a: input ndarray, integer or float
+    :param b: input ndarray with the same shape and dtype as `a`
+    :param window: "gaussian" (11 elements per axis for sigma 1.5) or "uniform"
+    :param sigma: standard deviation of the gaussian window, the radius is 3.5 sigma
+    :param size: length of the uniform window on every axis
+    :param k1: luminance constant, C1 = (k1 * data_range) ** 2
+    :param k2: contrast constant, C2 = (k2 * data_range) ** 2
+    :param data_range: dynamic range of the data, defaults to max - min of the integer dtype,
+        required for float arrays
+    :param mode: border handling, one of "valid", "constant", "nearest", "reflect"
+    :param cval: value used outside the array with mode "constant"
+    :param full: also return the SSIM map
+    :return: mean SSIM, or (mean SSIM, float64 SSIM map) when `full`
+    """
+    if a.dtype != b.dtype:
+        raise ValueError(f"both arrays must have the same dtype, got {a.dtype} and {b.dtype}")
+    if data_range is None and numpy.issubdtype(a.dtype, numpy.floating):
+        raise ValueError("data_range is required for float arrays")
+    if data_range is None:
+        ssim_map = _dtype_function("ssim_map", a)(a, b, window, sigma, size, k1, k2, mode, cval)
+    else:
+        ssim_map = _nd_thread_window_subspace.ssim_map_float(a.astype(numpy.float64, copy=False),
+                                                             b.astype(numpy.float64, copy=False),
+                                                             data_range, window, sigma, size, k1, k2, mode,
+                                                             cval)
+    mean = float(ssim_map.mean())
+    return (mean, ssim_map) if full else mean
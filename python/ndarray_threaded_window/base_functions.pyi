@@ -19,3 +19,7 @@ def fast_std_clamp(array: numpy.ndarray) -> int:
 
 def area_contrast(array: numpy.ndarray) -> int:
     ...
+
+
+def michelson_contrast(array: numpy.ndarray) -> int:
+    ...
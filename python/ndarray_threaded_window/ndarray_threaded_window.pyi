@@ -4,15 +4,22 @@ base_functions = base_functions
 
 
 # func_area_contrast: int
-# """ func_area_contrast currently does not work as rms   """
+# """ func_area_contrast RMS contrast, the population standard deviation of the window
+#
+#     the same value as func_fast_population_std, rounded to the input dtype"""
+#
+# func_exact_population_std: int
+# """ func_exact_population_std population standard deviation with integer arithmetic only
+#
+#     the square root is an exact integer square root, so the result is the same on every platform,
+#     rounded half up or with the rounding of the conversion preset,
+#     float arrays use :py:obj:`func_fast_population_std`"""
 #
 # func_fast_std: int
 # """ func_fast_std standard deviation function which uses integer addition.
 #
 #     fast_std is around twice as fast as func_stdev_ddof_0,
-#     for most inputs of u8, u16, and u32 it should be within rounding error
-#
-#     note: may overflow on u64 dtypes"""
+#     for most inputs of u8, u16, and u32 it should be within rounding error"""
 #
 # func_fast_std_clamp: int
 # """ func_fast_std_clamp the same as :py:obj:`func_area_contrast` * 2
@@ -20,11 +27,17 @@ base_functions = base_functions
 #     this ensures the minimum and maximum return from an unsigned integer type are between u::MIN and u::MAX
 # """
 #
+# func_michelson_contrast: int
+# """ func_michelson_contrast (max - min) / (max + min) scaled so 1.0 is the maximum of the input dtype,
+#     signed integers are offset by the minimum of the dtype first"""
+#
 # func_stdev_ddof_0: int
-# """ func_stdev_ddof_0 converts to float64 then apply Welford one-pass algorithm from (rust ndarray)[https://docs.rs/ndarray/0.15.6/ndarray/struct.ArrayBase.html#method.std] """
+# """ func_stdev_ddof_0 converts to float64 then sums with the pairwise summation of numpy, the same result as
+#     `numpy.std(window, ddof=0)`, `int(numpy.std(window))` with the "numpy" conversion preset"""
 #
 # func_stdev_ddof_1: int
-# """ func_stdev_ddof_1 converts to float64 then apply Welford one-pass algorithm from (rust ndarray)[https://docs.rs/ndarray/0.15.6/ndarray/struct.ArrayBase.html#method.std]"""
+# """ func_stdev_ddof_1 converts to float64 then sums with the pairwise summation of numpy, the same result as
+#     `numpy.std(window, ddof=1)`, `int(numpy.std(window, ddof=1))` with the "numpy" conversion preset"""
 #
 
 def set_thread_count_envar(threads: int):
@@ -1,13 +1,13 @@
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc};
 use std::thread;
 
-use ndarray::{Array, ArrayView, AssignElem, Dimension, RemoveAxis};
+use ndarray::{Array, ArrayView, AssignElem, Dimension, RemoveAxis, Zip};
 use num_traits::Zero;
 
 
 use crate::array_shape_traits::{ArraySplitter,WinSh};
-use crate::integer_conversion_traits::IntConv;
-use crate::window_functions::WinFunc;
+use crate::integer_conversion_traits::{AccumulationOverflow, ConversionError, ConversionPolicy, WinNum};
+use crate::window_functions::{ConvertedWinFunc, WinFunc};
 
 
 ///
@@ -98,20 +98,373 @@ where
     splitter.restack(array_stacks)
 }
 
+/// `true` when the sum of `w` overflows [WinNum::Acc], the elements are only added when the
+/// window volume makes an overflow possible, see [WinNum::acc_may_overflow]
+#[inline]
+pub fn window_sum_overflows<T, D>(w: &ArrayView<T, D>) -> bool
+where
+    T: WinNum,
+    D: Dimension,
+{
+    T::acc_may_overflow(w.len()) && w.iter().try_fold(T::ACC_ZERO, T::acc_checked_add).is_none()
+}
+
+/// `Err` when the sum of any window of `arr` overflows [WinNum::Acc]
+///
+/// the built-in window functions which sum the window (see
+/// [get_func_accumulates](crate::window_functions::get_func_accumulates)) fall back to [f64] sums
+/// instead, see [sum_as_f64](crate::window_functions::sum_as_f64)
+///
+/// # example:
+/// ```
+/// use ndarray::{arr1, Ix1};
+/// assert!(check_window_sums(arr1(&[1u8, 2, 3]).view(), Ix1(2)).is_ok());
+/// assert!(check_window_sums(arr1(&[i128::MAX, 1, 0]).view(), Ix1(2)).is_err());
+/// ```
+pub fn check_window_sums<T, D>(arr: ArrayView<T, D>, win_size: D) -> Result<(), AccumulationOverflow>
+where
+    T: WinNum,
+    D: Dimension,
+{
+    if !T::acc_may_overflow(win_size.size()) {
+        return Ok(());
+    }
+    match arr.windows(win_size).into_iter().any(|w| window_sum_overflows(&w)) {
+        true => Err(AccumulationOverflow),
+        false => Ok(()),
+    }
+}
+
+/// [thread_over_any_window] which returns an error when a window sum overflows instead of
+/// falling back to [f64], see [window_sum_overflows]
+///
+/// the windows are only checked when `accumulates` is `true`, pass
+/// [get_func_accumulates](crate::window_functions::get_func_accumulates) for a built-in function
+pub fn thread_over_any_window_checked<T, D>(
+    input_array: Array<T, D>,
+    win_size: D,
+    func: WinFunc<T, D>,
+    accumulates: bool,
+) -> Result<Array<T, D>, AccumulationOverflow>
+where
+    T: WinNum + Zero + Copy + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    if !accumulates || !T::acc_may_overflow(win_size.size()) {
+        return Ok(thread_over_any_window(input_array, win_size, func));
+    }
+    let values = thread_over_any_window_map(input_array, win_size, move |w| match window_sum_overflows(&w) {
+        true => None,
+        false => Some(func(w)),
+    });
+    let out = values.iter().cloned().collect::<Option<Vec<T>>>().ok_or(AccumulationOverflow)?;
+    Ok(Array::from_shape_vec(values.raw_dim(), out).unwrap())
+}
+
+/// multi threaded version of [apply_over_any_window] for a [ConvertedWinFunc],
+/// every window result is written into `T` with `policy`
+///
+/// the first result which does not fit is returned as the error with [Overflow::Error](crate::integer_conversion_traits::Overflow::Error)
+///
+/// # example:
+/// ```
+/// use ndarray::{arr1, Ix1};
+/// use ndarray_threaded_window::integer_conversion_traits::ConversionPolicy;
+/// use ndarray_threaded_window::window_functions::window_std;
+/// let array = arr1(&[0u8, 3, 0, 3]);
+/// let std = |w, p: ConversionPolicy| p.convert(window_std(w, 0));
+/// let out = thread_over_any_window_converted(array, Ix1(2), std, ConversionPolicy::NUMPY);
+/// assert_eq!(out, Ok(arr1(&[1, 1, 1])));
+/// ```
+pub fn thread_over_any_window_converted<T, D>(
+    input_array: Array<T, D>,
+    win_size: D,
+    func: ConvertedWinFunc<T, D>,
+    policy: ConversionPolicy,
+) -> Result<Array<T, D>, ConversionError>
+where
+    T: WinNum + Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let values = thread_over_any_window_map(input_array, win_size, move |w| func(w, policy));
+    let out = values.iter().cloned().collect::<Result<Vec<T>, _>>()?;
+    Ok(Array::from_shape_vec(values.raw_dim(), out).unwrap())
+}
+
+/// the same as [apply_over_any_window] but `func` can be any closure and the output element type
+/// does not need to match the input element type
+///
+/// used when a window function needs extra parameters (a footprint, a kernel, a threshold ...)
+/// which can't be passed through a plain [WinFunc]
+pub fn apply_over_any_window_map<T, O, D, F>(arr: Array<T, D>, win_size: D, func: &F) -> Array<O, D>
+where
+    D: Dimension + WinSh,
+    F: Fn(ArrayView<T, D>) -> O,
+{
+    let new_size = arr.raw_dim().size_sub_shape(&win_size);
+    // windows are iterated in logical order, so the output can be built straight from the vec
+    let out: Vec<O> = arr.windows(win_size).into_iter().map(func).collect();
+    Array::from_shape_vec(new_size, out).unwrap()
+}
+
+/// multi threaded version of [apply_over_any_window_map]
+///
+/// splits the array with [ArraySplitter] in the same way as [thread_over_any_window],
+/// `func` is shared between the threads so it must be `Send + Sync`
+///
+/// # example:
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let array: Array2<u8>= Array2::zeros((50, 5));
+/// let window: Ix2 = Ix2::from_slice(&[2,2]);
+/// let offset = 3u16;
+/// let out = thread_over_any_window_map(array, window, move |w| w.sum() as u16 + offset);
+/// assert_eq!(out.shape(), &[49,4])
+/// ```
+pub fn thread_over_any_window_map<T, O, D, F>(
+    input_array: Array<T, D>,
+    win_size: D,
+    func: F,
+) -> Array<O, D>
+where
+    T: Clone + Send + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(ArrayView<T, D>) -> O + Send + Sync + 'static,
+{
+    thread_over_window_chunks(input_array, win_size, move |chunk, win_size| {
+        apply_over_any_window_map(chunk, win_size, &func)
+    })
+}
+
+/// split the array with [ArraySplitter] and run `func` on each overlapping chunk in its own thread,
+/// `func` gets the chunk and the window shape and must return the "valid" window output of the chunk
+///
+/// used by window functions which carry state from one window to the next (a sliding histogram ...)
+/// instead of looking at every window on its own
+pub fn thread_over_window_chunks<T, O, D, F>(input_array: Array<T, D>, win_size: D, func: F) -> Array<O, D>
+where
+    T: Clone + Send + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(Array<T, D>, D) -> Array<O, D> + Send + Sync + 'static,
+{
+    let splitter = ArraySplitter::new(&input_array, &win_size);
+    thread_over_splits(
+        splitter,
+        |a, b, d| D::slice_array(&input_array, a, b, d).to_owned(),
+        move |chunk| func(chunk, win_size),
+    )
+}
+
+/// run `func` in its own thread on the chunk `split` cuts out for each `(start, stop, axis)` of
+/// `splitter`, then restack the outputs in order
+fn thread_over_splits<C, O, D, S, F>(splitter: ArraySplitter, split: S, func: F) -> Array<O, D>
+where
+    C: Send + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + 'static,
+    S: Fn(&usize, &usize, &usize) -> C,
+    F: Fn(C) -> Array<O, D> + Send + Sync + 'static,
+{
+    let func = Arc::new(func);
+
+    let mut thread_workers: Vec<_> = vec![];
+    for (a, b, d) in splitter.slice_position_vec().iter() {
+        let (tx, rx) = mpsc::channel();
+        let pre_compute_chunk = split(a, b, d);
+        let thread_func = Arc::clone(&func);
+        thread::spawn(move || {
+            let computed_array_output = thread_func(pre_compute_chunk);
+            tx.send(computed_array_output).unwrap();
+        });
+        thread_workers.push(rx);
+    }
+
+    let array_stacks: Vec<_> = thread_workers.iter().map(|rx| rx.recv().unwrap()).collect();
+    splitter.restack(array_stacks)
+}
+
+/// the same as [apply_over_any_window_map] but over `N` arrays of the same shape at once,
+/// `func` gets the windows at the same position of every array, in the order of `arrays`
+///
+/// # example:
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let a: Array2<u8> = Array2::ones((5, 5));
+/// let b: Array2<u8> = Array2::ones((5, 5));
+/// let out = apply_over_windows_lockstep([a, b], Ix2(2, 2), &|[wa, wb]| wa.sum() + wb.sum());
+/// assert_eq!(out, Array2::from_elem((4, 4), 8));
+/// ```
+pub fn apply_over_windows_lockstep<T, O, D, F, const N: usize>(
+    arrays: [Array<T, D>; N],
+    win_size: D,
+    func: &F,
+) -> Array<O, D>
+where
+    D: Dimension + WinSh,
+    F: Fn([ArrayView<T, D>; N]) -> O,
+{
+    assert!(
+        arrays.iter().all(|a| a.shape() == arrays[0].shape()),
+        "arrays must all have the same shape"
+    );
+    let new_size = arrays[0].raw_dim().size_sub_shape(&win_size);
+    let mut windows = arrays.each_ref().map(|a| a.windows(win_size.clone()).into_iter());
+    let out: Vec<O> = (0..new_size.size())
+        .map(|_| func(std::array::from_fn(|k| windows[k].next().unwrap())))
+        .collect();
+    Array::from_shape_vec(new_size, out).unwrap()
+}
+
+/// multi threaded version of [apply_over_windows_lockstep]
+///
+/// the split found by [ArraySplitter] for the first array is applied to every array, so each thread
+/// gets the matching chunk of all of them
+pub fn thread_over_windows_lockstep<T, O, D, F, const N: usize>(
+    arrays: [Array<T, D>; N],
+    win_size: D,
+    func: F,
+) -> Array<O, D>
+where
+    T: Clone + Send + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn([ArrayView<T, D>; N]) -> O + Send + Sync + 'static,
+{
+    assert!(
+        arrays.iter().all(|a| a.shape() == arrays[0].shape()),
+        "arrays must all have the same shape"
+    );
+    let splitter = ArraySplitter::new(&arrays[0], &win_size);
+    thread_over_splits(
+        splitter,
+        |a, b, d| arrays.each_ref().map(|arr| D::slice_array(arr, a, b, d).to_owned()),
+        move |chunks| apply_over_windows_lockstep(chunks, win_size, &func),
+    )
+}
+
+/// `true` for every element of `arr` which is not `nodata`, a NaN `nodata` marks every NaN
+///
+/// the validity mask used by [apply_over_any_window_masked], combine it with a separate mask with `&`
+pub fn nodata_mask<T, D>(arr: &Array<T, D>, nodata: &T) -> Array<bool, D>
+where
+    T: PartialEq,
+    D: Dimension,
+{
+    #[allow(clippy::eq_op)]
+    let nodata_is_nan = nodata != nodata;
+    #[allow(clippy::eq_op)]
+    arr.map(|x| !(x == nodata || (nodata_is_nan && x != x)))
+}
+
+/// `func` over the valid elements of the window and the number of valid elements,
+/// the valid elements are laid out along the first axis so any [WinFunc] which does not depend on
+/// the position of an element can reduce over them
+#[inline]
+fn reduce_valid<T, O, D, F>(w: ArrayView<(T, bool), D>, func: &F, nodata: &O, min_valid: usize) -> (O, usize)
+where
+    T: Clone,
+    O: Clone,
+    D: Dimension,
+    F: Fn(ArrayView<T, D>) -> O,
+{
+    let valid: Vec<T> = w.iter().filter(|(_, v)| *v).map(|(x, _)| x.clone()).collect();
+    let n = valid.len();
+    if n < min_valid.max(1) {
+        return (nodata.clone(), n);
+    }
+    let mut shape = D::zeros(w.ndim());
+    shape.slice_mut().fill(1);
+    shape[0] = n;
+    (func(ArrayView::from_shape(shape, &valid).unwrap()), n)
+}
+
+/// pair each element with its validity, panics when the shapes differ
+fn zip_mask<T, D>(arr: Array<T, D>, mask: &Array<bool, D>) -> Array<(T, bool), D>
+where
+    T: Clone,
+    D: Dimension,
+{
+    assert_eq!(arr.shape(), mask.shape(), "mask must have the same shape as the array");
+    Zip::from(&arr).and(mask).map_collect(|x, &v| (x.clone(), v))
+}
+
+/// the same as [apply_over_any_window] but elements where `mask` is `false` are left out of every
+/// window, for rasters with a nodata value (see [nodata_mask]) or a separate validity mask
+///
+/// windows with fewer than `min_valid` (at least one) valid elements are set to `nodata`,
+/// the second array is the number of valid elements in each window
+///
+/// `func` is usually a [WinFunc] but can be any closure, the output type follows `nodata`
+///
+/// # example:
+/// ```
+/// use ndarray::{arr1, Ix1};
+/// use ndarray_threaded_window::window_functions::func_window_min;
+/// let array = arr1(&[5u8, 0, 7, 0, 0, 3]);
+/// let mask = nodata_mask(&array, &0);
+/// let (out, count) = apply_over_any_window_masked(array, &mask, Ix1(3), func_window_min, 0, 1);
+/// assert_eq!(out, arr1(&[5, 7, 7, 3]));
+/// assert_eq!(count, arr1(&[2, 1, 1, 1]));
+/// ```
+pub fn apply_over_any_window_masked<T, O, D, F>(
+    arr: Array<T, D>,
+    mask: &Array<bool, D>,
+    win_size: D,
+    func: F,
+    nodata: O,
+    min_valid: usize,
+) -> (Array<O, D>, Array<usize, D>)
+where
+    T: Clone,
+    O: Clone,
+    D: Dimension + WinSh,
+    F: Fn(ArrayView<T, D>) -> O,
+{
+    let pairs = apply_over_any_window_map(zip_mask(arr, mask), win_size, &|w| {
+        reduce_valid(w, &func, &nodata, min_valid)
+    });
+    (pairs.map(|(x, _)| x.clone()), pairs.mapv(|(_, n)| n))
+}
+
+/// multi threaded version of [apply_over_any_window_masked]
+pub fn thread_over_any_window_masked<T, O, D, F>(
+    input_array: Array<T, D>,
+    mask: &Array<bool, D>,
+    win_size: D,
+    func: F,
+    nodata: O,
+    min_valid: usize,
+) -> (Array<O, D>, Array<usize, D>)
+where
+    T: Clone + Send + Sync + 'static,
+    O: Clone + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(ArrayView<T, D>) -> O + Send + Sync + 'static,
+{
+    let pairs = thread_over_any_window_map(zip_mask(input_array, mask), win_size, move |w| {
+        reduce_valid(w, &func, &nodata, min_valid)
+    });
+    (pairs.map(|(x, _)| x.clone()), pairs.mapv(|(_, n)| n))
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::BitXor;
 
 
-    use ndarray::{Array, Ix1, Ix2, Ix3, Ix4, Ix5};
+    use ndarray::{s, Array, Ix1, Ix2, Ix3, Ix4, Ix5};
 
     use crate::array_shape_traits::WinSh;
 
     use crate::array_threading::{
-        apply_over_any_window, thread_over_any_window
+        apply_over_any_window, apply_over_any_window_masked, apply_over_windows_lockstep, nodata_mask,
+        check_window_sums, thread_over_any_window, thread_over_any_window_checked,
+        thread_over_any_window_converted, thread_over_any_window_masked, thread_over_windows_lockstep,
     };
-    use crate::window_functions::func_fast_std;
-    use crate::integer_conversion_traits::IntConv;
+    use crate::window_functions::{func_fast_population_std, func_fast_std, func_window_max, get_func, get_func_accumulates, get_func_converted};
+    use crate::integer_conversion_traits::{AccumulationOverflow, ConversionPolicy, IntConv, Overflow, Rounding, WinNum};
 
     const SL1: usize = 24;
     const SL2: usize = 60;
@@ -229,4 +582,109 @@ mod tests {
         assert_eq!(oc, sc);
     }
 
+
+    #[test]
+    fn lockstep_matches_single_thread() {
+        let win = Ix3::from_slice(WIN_SHAPE);
+        let a = gen3::<u8>();
+        let b = a.mapv(|x| x.wrapping_mul(7));
+        let diff = |[wa, wb]: [ndarray::ArrayView3<u8>; 2]| {
+            wa.iter().zip(wb.iter()).map(|(&x, &y)| x as i32 - y as i32).sum::<i32>()
+        };
+        let threaded = thread_over_windows_lockstep([a.clone(), b.clone()], win, diff);
+        let single = apply_over_windows_lockstep([a, b], win, &diff);
+        assert_eq!(threaded, single);
+        assert_eq!(threaded.shape(), &[SL1 - 1, SL2 - 3, SL3 - 2]);
+    }
+
+    #[test]
+    fn masked_matches_single_thread() {
+        let win = Ix2::from_slice(WIN_SHAPE);
+        let a = gen2::<u16>();
+        let mask = nodata_mask(&a, &0) & a.mapv(|x| x % 5 != 1);
+        let (threaded, threaded_count) = thread_over_any_window_masked(a.clone(), &mask, win, func_window_max, 0, 5);
+        let (single, single_count) = apply_over_any_window_masked(a.clone(), &mask, win, func_window_max, 0, 5);
+        assert_eq!(threaded, single);
+        assert_eq!(threaded_count, single_count);
+        for ((w, m), (&x, &n)) in a.windows(win).into_iter().zip(mask.windows(win)).zip(single.iter().zip(single_count.iter())) {
+            let valid: Vec<u16> = w.iter().zip(m.iter()).filter(|(_, &v)| v).map(|(&x, _)| x).collect();
+            assert_eq!(n, valid.len());
+            match n < 5 {
+                true => assert_eq!(x, 0),
+                false => assert_eq!(x, *valid.iter().max().unwrap()),
+            }
+        }
+        let nan = Array::from_shape_fn(SL1, |a| if a % 3 == 0 { f32::NAN } else { a as f32 });
+        let (out, count) = apply_over_any_window_masked(nan.clone(), &nodata_mask(&nan, &f32::NAN), Ix1(2), func_fast_population_std, f32::NAN, 2);
+        assert!(out[0].is_nan() && out[1] == 0.5);
+        assert_eq!(count[0], 1);
+    }
+
+    #[test]
+    fn converted_matches_builtins() {
+        let win = Ix2::from_slice(WIN_SHAPE);
+        let a = gen2::<u8>();
+        let mut i = 0;
+        while let Ok(func) = get_func::<u8, Ix2>(i) {
+            if let Some(converted) = get_func_converted::<u8, Ix2>(i).unwrap() {
+                let expected = thread_over_any_window(a.clone(), win, func);
+                let out = thread_over_any_window_converted(a.clone(), win, converted, ConversionPolicy::DEFAULT);
+                assert_eq!(out, Ok(expected.clone()), "function {}", i);
+                let floor = ConversionPolicy { rounding: Rounding::Floor, overflow: Overflow::Saturate };
+                let floored = thread_over_any_window_converted(a.clone(), win, converted, floor).unwrap();
+                let ceil = ConversionPolicy { rounding: Rounding::Ceil, overflow: Overflow::Saturate };
+                let ceiled = thread_over_any_window_converted(a.clone(), win, converted, ceil).unwrap();
+                for ((&f, &c), &x) in floored.iter().zip(ceiled.iter()).zip(expected.iter()) {
+                    assert!(f <= x && x <= c && c - f <= 1, "function {}", i);
+                }
+            }
+            i += 1;
+        }
+        assert_eq!(i, 11);
+        // func_fast_std_clamp doubles the std of a full range window past i8::MAX
+        let wide = Array::from_shape_fn(SL1, |a| if a % 2 == 0 { i8::MIN } else { i8::MAX });
+        let clamp = get_func_converted::<i8, Ix1>(6).unwrap().unwrap();
+        assert!(thread_over_any_window_converted(wide.clone(), Ix1(2), clamp, ConversionPolicy::NUMPY).is_err());
+
+        // the exact std of [i8::MIN, i8::MAX] is 127.5
+        let exact = get_func_converted::<i8, Ix1>(10).unwrap().unwrap();
+        let half_error = ConversionPolicy { rounding: Rounding::HalfAway, overflow: Overflow::Error };
+        assert!(thread_over_any_window_converted(wide.clone(), Ix1(2), exact, half_error).is_err());
+        let numpy = thread_over_any_window_converted(wide.clone(), Ix1(2), exact, ConversionPolicy::NUMPY);
+        assert_eq!(numpy, Ok(Array::from_elem(SL1 - 1, 127)));
+        let odd = Array::from_shape_fn(SL1, |a| (a % 2) as i8 * 3);
+        let floor = ConversionPolicy { rounding: Rounding::Floor, overflow: Overflow::Error };
+        let half_even = ConversionPolicy { rounding: Rounding::HalfEven, overflow: Overflow::Error };
+        assert_eq!(thread_over_any_window_converted(odd.clone(), Ix1(2), exact, floor), Ok(Array::from_elem(SL1 - 1, 1)));
+        assert_eq!(thread_over_any_window_converted(odd, Ix1(2), exact, half_even), Ok(Array::from_elem(SL1 - 1, 2)));
+        let (masked, _) = thread_over_any_window_masked(wide.clone(), &nodata_mask(&wide, &0), Ix1(2), move |w| Some(exact(w, half_error)), None, 2);
+        assert!(masked.iter().all(|x| matches!(x, Some(Err(_)))));
+    }
+
+    #[test]
+    fn checked_sums() {
+        assert!(!u8::acc_may_overflow(1 << 40));
+        assert!(u32::acc_may_overflow(1 << 32));
+        assert!(!i128::acc_may_overflow(1) && i128::acc_may_overflow(2));
+        assert!(!f32::acc_may_overflow(usize::MAX));
+
+        let win = Ix2::from_slice(WIN_SHAPE);
+        let a = gen2::<i64>();
+        assert_eq!(thread_over_any_window_checked(a.clone(), win, func_window_max, true), Ok(thread_over_any_window(a, win, func_window_max)));
+
+        let b = Array::from_shape_fn(SL1, |a| if a == 20 { i128::MAX } else { a as i128 });
+        assert_eq!(check_window_sums(b.slice(s![..20]), Ix1(3)), Ok(()));
+        assert_eq!(check_window_sums(b.view(), Ix1(3)), Err(AccumulationOverflow));
+        assert_eq!(thread_over_any_window_checked(b.clone(), Ix1(3), func_fast_population_std, true), Err(AccumulationOverflow));
+        // max and min don't sum the window, so only the functions which accumulate are checked
+        for (i, expect_ok) in [(0, true), (1, true), (7, false), (9, true)] {
+            let checked = thread_over_any_window_checked(b.clone(), Ix1(3), get_func(i).unwrap(), get_func_accumulates(i).unwrap());
+            assert_eq!(checked.is_ok(), expect_ok, "function {}", i);
+        }
+        assert_eq!(
+            thread_over_any_window_checked(b.clone(), Ix1(3), func_window_max, false),
+            Ok(thread_over_any_window(b, Ix1(3), func_window_max))
+        );
+    }
+
 }
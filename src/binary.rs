@@ -0,0 +1,313 @@
+use ndarray::{Array, Array2, ArrayView, Dimension, Ix2, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::{thread_over_any_window_map, thread_over_window_chunks};
+use crate::border_handling::{centred_padding, pad_array, BorderMode};
+
+/// how the footprint of a binary operation combines the elements under it
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combine {
+    /// every element under the footprint is `true`, erosion
+    All,
+    /// at least one element under the footprint is `true`, dilation
+    Any,
+}
+
+/// number of bits in a packed word
+const WORD: usize = u64::BITS as usize;
+
+/// rows of a 2-D bool array packed 64 columns per word, bit `k` of word `w` is column `64 * w + k`
+struct PackedRows {
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl PackedRows {
+    fn new(arr: &Array2<bool>) -> Self {
+        let words_per_row = arr.ncols().div_ceil(WORD);
+        let mut words = vec![0u64; arr.nrows() * words_per_row];
+        for (r, row) in arr.rows().into_iter().enumerate() {
+            let packed = &mut words[r * words_per_row..(r + 1) * words_per_row];
+            for (c, &x) in row.iter().enumerate() {
+                packed[c / WORD] |= (x as u64) << (c % WORD);
+            }
+        }
+        PackedRows { words_per_row, words }
+    }
+
+    #[inline]
+    fn row(&self, r: usize) -> &[u64] {
+        &self.words[r * self.words_per_row..(r + 1) * self.words_per_row]
+    }
+}
+
+/// word `w` of `row` moved `shift` columns towards column 0, columns past the end are `0`
+#[inline]
+fn shifted_word(row: &[u64], w: usize, shift: usize) -> u64 {
+    let (q, r) = (w + shift / WORD, shift % WORD);
+    let lo = row.get(q).copied().unwrap_or(0);
+    match r {
+        0 => lo,
+        _ => (lo >> r) | (row.get(q + 1).copied().unwrap_or(0) << (WORD - r)),
+    }
+}
+
+/// offsets of the `true` elements of a 2-D footprint
+fn footprint_offsets(footprint: &Array2<bool>) -> Vec<(usize, usize)> {
+    footprint
+        .indexed_iter()
+        .filter(|(_, &m)| m)
+        .map(|(ix, _)| ix)
+        .collect()
+}
+
+/// "valid" binary erosion or dilation of a 2-D chunk, one word of 64 output columns at a time
+fn packed_combine(chunk: Array2<bool>, footprint: &Array2<bool>, combine: Combine) -> Array2<bool> {
+    let (out_rows, out_cols) = (
+        chunk.nrows() + 1 - footprint.nrows(),
+        chunk.ncols() + 1 - footprint.ncols(),
+    );
+    let packed = PackedRows::new(&chunk);
+    let offsets = footprint_offsets(footprint);
+    let out_words = out_cols.div_ceil(WORD);
+    let mut out = Array2::from_elem((out_rows, out_cols), false);
+    let mut acc = vec![0u64; out_words];
+    for (r, mut out_row) in out.rows_mut().into_iter().enumerate() {
+        acc.fill(match combine {
+            Combine::All => u64::MAX,
+            Combine::Any => 0,
+        });
+        for &(i, j) in offsets.iter() {
+            let row = packed.row(r + i);
+            for (w, a) in acc.iter_mut().enumerate() {
+                let x = shifted_word(row, w, j);
+                match combine {
+                    Combine::All => *a &= x,
+                    Combine::Any => *a |= x,
+                }
+            }
+        }
+        for (c, o) in out_row.iter_mut().enumerate() {
+            *o = acc[c / WORD] >> (c % WORD) & 1 == 1;
+        }
+    }
+    out
+}
+
+/// "valid" count of the `true` elements under the footprint of a 2-D chunk
+///
+/// each word of 64 columns keeps its counts in bit planes, plane `p` holds bit `p` of every count,
+/// so adding a shifted row is a ripple carry over the planes instead of 64 separate additions
+fn packed_count(chunk: Array2<bool>, footprint: &Array2<bool>) -> Array2<usize> {
+    let (out_rows, out_cols) = (
+        chunk.nrows() + 1 - footprint.nrows(),
+        chunk.ncols() + 1 - footprint.ncols(),
+    );
+    let packed = PackedRows::new(&chunk);
+    let offsets = footprint_offsets(footprint);
+    let planes = (usize::BITS - offsets.len().leading_zeros()).max(1) as usize;
+    let out_words = out_cols.div_ceil(WORD);
+    let mut out = Array2::zeros((out_rows, out_cols));
+    let mut counts = vec![0u64; out_words * planes];
+    for (r, mut out_row) in out.rows_mut().into_iter().enumerate() {
+        counts.fill(0);
+        for &(i, j) in offsets.iter() {
+            let row = packed.row(r + i);
+            for (w, word_planes) in counts.chunks_exact_mut(planes).enumerate() {
+                let mut carry = shifted_word(row, w, j);
+                for p in word_planes.iter_mut() {
+                    if carry == 0 {
+                        break;
+                    }
+                    (*p, carry) = (*p ^ carry, *p & carry);
+                }
+            }
+        }
+        for (c, o) in out_row.iter_mut().enumerate() {
+            let word_planes = &counts[(c / WORD) * planes..(c / WORD + 1) * planes];
+            *o = word_planes
+                .iter()
+                .enumerate()
+                .fold(0usize, |a, (p, x)| a | (((x >> (c % WORD)) & 1) as usize) << p);
+        }
+    }
+    out
+}
+
+/// pad for `border`, then run the packed 2-D path when the array has two axes,
+/// otherwise `fallback` over every window
+fn binary_apply<O, D, P, F>(
+    arr: Array<bool, D>,
+    footprint: &Array<bool, D>,
+    (before, after): (D, D),
+    border: BorderMode<bool>,
+    packed: P,
+    fallback: F,
+) -> Array<O, D>
+where
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    P: Fn(Array2<bool>, &Array2<bool>) -> Array2<O> + Send + Sync + 'static,
+    F: Fn(ArrayView<bool, D>, &Array<bool, D>) -> O + Send + Sync + 'static,
+{
+    let arr = match border {
+        BorderMode::Valid => arr,
+        _ => pad_array(&arr, &before, &after, &border),
+    };
+    let win_size = footprint.raw_dim();
+    match (arr.view().into_dimensionality::<Ix2>(), footprint.view().into_dimensionality::<Ix2>()) {
+        (Ok(arr2), Ok(fp2)) => {
+            let fp2 = fp2.to_owned();
+            let out = thread_over_window_chunks(arr2.to_owned(), fp2.raw_dim(), move |chunk, _| {
+                packed(chunk, &fp2)
+            });
+            out.into_dimensionality::<D>().unwrap()
+        }
+        _ => {
+            let fp = footprint.clone();
+            thread_over_any_window_map(arr, win_size, move |w| fallback(w, &fp))
+        }
+    }
+}
+
+/// binary erosion, `true` where every element under the footprint centred on it is `true`
+///
+/// 2-D arrays are bit-packed 64 columns per word and threaded over
+/// [ArraySplitter](crate::array_shape_traits::ArraySplitter) chunks,
+/// `BorderMode::Constant(true)` keeps objects touching the edge
+///
+/// ```
+/// use ndarray::arr2;
+/// let a = arr2(&[[true, true, true], [true, true, true], [false, true, true]]);
+/// let fp = rectangle_footprint(ndarray::Ix2(2, 2));
+/// let eroded = binary_erosion(a, &fp, BorderMode::Valid);
+/// assert_eq!(eroded, arr2(&[[true, true], [false, true]]));
+/// ```
+pub fn binary_erosion<D>(arr: Array<bool, D>, footprint: &Array<bool, D>, border: BorderMode<bool>) -> Array<bool, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let padding = centred_padding(&footprint.raw_dim());
+    binary_apply(
+        arr,
+        footprint,
+        padding,
+        border,
+        |chunk, fp| packed_combine(chunk, fp, Combine::All),
+        |w, fp| w.iter().zip(fp.iter()).all(|(&x, &m)| x || !m),
+    )
+}
+
+/// binary dilation, `true` where any element under the reflected footprint centred on it is `true`
+///
+/// see [binary_erosion] for the 2-D fast path
+pub fn binary_dilation<D>(arr: Array<bool, D>, footprint: &Array<bool, D>, border: BorderMode<bool>) -> Array<bool, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let fp = footprint.slice_each_axis(|_| ndarray::Slice::new(0, None, -1)).to_owned();
+    // the reflected footprint is centred on `window - 1 - window / 2`
+    let (before, after) = centred_padding(&fp.raw_dim());
+    binary_apply(
+        arr,
+        &fp,
+        (after, before),
+        border,
+        |chunk, fp| packed_combine(chunk, fp, Combine::Any),
+        |w, fp| w.iter().zip(fp.iter()).any(|(&x, &m)| x && m),
+    )
+}
+
+/// erosion followed by dilation, removes foreground details smaller than the footprint
+///
+/// with [BorderMode::Valid] the output shrinks by `2 * (window - 1)` on every axis
+pub fn binary_opening<D>(arr: Array<bool, D>, footprint: &Array<bool, D>, border: BorderMode<bool>) -> Array<bool, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    binary_dilation(binary_erosion(arr, footprint, border), footprint, border)
+}
+
+/// dilation followed by erosion, fills background holes smaller than the footprint
+///
+/// with [BorderMode::Valid] the output shrinks by `2 * (window - 1)` on every axis
+pub fn binary_closing<D>(arr: Array<bool, D>, footprint: &Array<bool, D>, border: BorderMode<bool>) -> Array<bool, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    binary_erosion(binary_dilation(arr, footprint, border), footprint, border)
+}
+
+/// number of `true` elements under the footprint centred on each element, the element itself is
+/// counted when the centre of the footprint is `true`
+///
+/// 2-D arrays count 64 columns at once with bit-sliced counters, see [binary_erosion]
+pub fn neighbour_count<D>(arr: Array<bool, D>, footprint: &Array<bool, D>, border: BorderMode<bool>) -> Array<usize, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let padding = centred_padding(&footprint.raw_dim());
+    binary_apply(arr, footprint, padding, border, packed_count, |w, fp| {
+        w.iter().zip(fp.iter()).filter(|(&x, &m)| x && m).count()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array3, Ix2, Ix3};
+
+    use crate::array_threading::apply_over_any_window_map;
+    use crate::morphology::rectangle_footprint;
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn packed_matches_window_reference() {
+        // wider than a word so the shifts cross word boundaries
+        let a: Array2<bool> = Array2::from_shape_fn((37, 150), |ix| !not_a_hash(ix).is_multiple_of(3));
+        let mut fp = rectangle_footprint(Ix2(3, 5));
+        fp[[0, 0]] = false;
+        fp[[2, 3]] = false;
+        let eroded = binary_erosion(a.clone(), &fp, BorderMode::Valid);
+        let counts = neighbour_count(a.clone(), &fp, BorderMode::Valid);
+        let reference = apply_over_any_window_map(a.clone(), Ix2(3, 5), &|w| {
+            w.iter().zip(fp.iter()).filter(|(&x, &m)| x && m).count()
+        });
+        assert_eq!(counts, reference);
+        assert_eq!(eroded, reference.mapv(|n| n == 13));
+        let dilated = binary_dilation(a, &fp, BorderMode::Constant(false));
+        assert_eq!(dilated.shape(), &[37, 150]);
+    }
+
+    #[test]
+    fn packed_matches_n_dimensional() {
+        let a: Array2<bool> = Array2::from_shape_fn((20, 70), |ix| !not_a_hash(ix).is_multiple_of(3));
+        let a3 = a.clone().into_shape((20, 70, 1)).unwrap();
+        let fp = rectangle_footprint(Ix2(3, 3));
+        let fp3 = rectangle_footprint(Ix3(3, 3, 1));
+        for border in [BorderMode::Valid, BorderMode::Reflect, BorderMode::Constant(true)] {
+            let opened = binary_opening(a.clone(), &fp, border);
+            let opened3: Array3<bool> = binary_opening(a3.clone(), &fp3, border);
+            assert_eq!(opened.into_shape(opened3.raw_dim()).unwrap(), opened3);
+            let closed = binary_closing(a.clone(), &fp, border);
+            let closed3 = binary_closing(a3.clone(), &fp3, border);
+            assert_eq!(closed.into_shape(closed3.raw_dim()).unwrap(), closed3);
+            let counts = neighbour_count(a.clone(), &fp, border);
+            let counts3 = neighbour_count(a3.clone(), &fp3, border);
+            assert_eq!(counts.into_shape(counts3.raw_dim()).unwrap(), counts3);
+        }
+    }
+
+    #[test]
+    fn opening_removes_specks() {
+        let mut a = Array2::from_elem((9, 9), false);
+        a.slice_mut(ndarray::s![2..7, 2..7]).fill(true);
+        a[[0, 8]] = true;
+        let fp = rectangle_footprint(Ix2(3, 3));
+        let opened = binary_opening(a.clone(), &fp, BorderMode::Constant(false));
+        a[[0, 8]] = false;
+        assert_eq!(opened, a);
+    }
+}
@@ -0,0 +1,152 @@
+use ndarray::{Array, Dimension, IntoDimension, Slice};
+
+/// how values outside of the array are treated when a window hangs over the edge
+///
+/// [BorderMode::Valid] is how [crate::array_threading::apply_over_any_window] has always behaved,
+/// every other mode pads the array first so the output keeps the input shape
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorderMode<T> {
+    /// only windows which fit entirely inside the array are used, output shrinks by `window - 1`
+    Valid,
+    /// pad with a fixed value: `k k k | a b c d | k k k`
+    Constant(T),
+    /// repeat the edge value: `a a a | a b c d | d d d`
+    Nearest,
+    /// mirror the array, including the edge value: `c b a | a b c d | d c b`
+    Reflect,
+}
+
+impl<T> BorderMode<T> {
+    /// convert the [BorderMode::Constant] value, used when an array is converted before padding
+    pub fn map_constant<U, F: FnOnce(T) -> U>(self, f: F) -> BorderMode<U> {
+        match self {
+            BorderMode::Valid => BorderMode::Valid,
+            BorderMode::Constant(k) => BorderMode::Constant(f(k)),
+            BorderMode::Nearest => BorderMode::Nearest,
+            BorderMode::Reflect => BorderMode::Reflect,
+        }
+    }
+}
+
+/// padding needed before and after each axis so a window centred on `window / 2` produces an
+/// output with the same shape as the input
+///
+/// ```
+/// use ndarray::Ix2;
+/// let (before, after) = centred_padding(&Ix2(3, 4));
+/// assert_eq!((before, after), (Ix2(1, 2), Ix2(1, 1)));
+/// ```
+pub fn centred_padding<D: Dimension>(win_size: &D) -> (D, D) {
+    let mut before = win_size.clone();
+    let mut after = win_size.clone();
+    for ax in 0..win_size.ndim() {
+        before[ax] = win_size[ax] / 2;
+        after[ax] = win_size[ax] - 1 - win_size[ax] / 2;
+    }
+    (before, after)
+}
+
+/// pad `arr` with `before[axis]` elements at the start and `after[axis]` elements at the end of
+/// every axis, [BorderMode::Valid] returns the array unchanged
+pub fn pad_array<T, D>(arr: &Array<T, D>, before: &D, after: &D, mode: &BorderMode<T>) -> Array<T, D>
+where
+    T: Clone,
+    D: Dimension,
+{
+    let src_dim = arr.raw_dim();
+    let mut padded_dim = src_dim.clone();
+    for ax in 0..src_dim.ndim() {
+        padded_dim[ax] += before[ax] + after[ax];
+    }
+
+    match mode {
+        BorderMode::Valid => arr.clone(),
+        BorderMode::Constant(k) => {
+            let mut out = Array::from_elem(padded_dim, k.clone());
+            out.slice_each_axis_mut(|ad| {
+                let ax = ad.axis.index();
+                Slice::from(before[ax]..before[ax] + src_dim[ax])
+            })
+            .assign(arr);
+            out
+        }
+        BorderMode::Nearest | BorderMode::Reflect => Array::from_shape_fn(padded_dim, |pattern| {
+            let mut ix = pattern.into_dimension();
+            for ax in 0..ix.ndim() {
+                let pos = ix[ax] as isize - before[ax] as isize;
+                ix[ax] = source_index(pos, src_dim[ax], mode);
+            }
+            arr[ix].clone()
+        }),
+    }
+}
+
+/// pad `arr` so that every element has a window centred on it, see [centred_padding]
+pub fn pad_for_window<T: Clone, D: Dimension>(arr: Array<T, D>, win_size: &D, border: BorderMode<T>) -> Array<T, D> {
+    match border {
+        BorderMode::Valid => arr,
+        _ => {
+            let (before, after) = centred_padding(win_size);
+            pad_array(&arr, &before, &after, &border)
+        }
+    }
+}
+
+/// crop `arr` around its centre down to `shape`, used to line the input up with "valid" outputs
+pub fn centre_crop<T: Clone, D: Dimension>(arr: &Array<T, D>, shape: &D) -> Array<T, D> {
+    arr.slice_each_axis(|ad| {
+        let ax = ad.axis.index();
+        let start = (ad.len - shape[ax]) / 2;
+        Slice::from(start..start + shape[ax])
+    })
+    .to_owned()
+}
+
+/// map a position outside of `0..len` back into the array
+#[inline]
+fn source_index<T>(pos: isize, len: usize, mode: &BorderMode<T>) -> usize {
+    let len = len as isize;
+    match mode {
+        BorderMode::Reflect => {
+            // reflection with the edge repeated has a period of 2 * len
+            let period = 2 * len;
+            let p = pos.rem_euclid(period);
+            (if p < len { p } else { period - 1 - p }) as usize
+        }
+        _ => pos.clamp(0, len - 1) as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Ix1, Ix2};
+
+    use super::*;
+
+    #[test]
+    fn pad_modes_1d() {
+        let a = arr1(&[1u8, 2, 3, 4]);
+        let (before, after) = (Ix1(3), Ix1(3));
+        assert_eq!(
+            pad_array(&a, &before, &after, &BorderMode::Constant(9)),
+            arr1(&[9, 9, 9, 1, 2, 3, 4, 9, 9, 9])
+        );
+        assert_eq!(
+            pad_array(&a, &before, &after, &BorderMode::Nearest),
+            arr1(&[1, 1, 1, 1, 2, 3, 4, 4, 4, 4])
+        );
+        assert_eq!(
+            pad_array(&a, &before, &after, &BorderMode::Reflect),
+            arr1(&[3, 2, 1, 1, 2, 3, 4, 4, 3, 2])
+        );
+        assert_eq!(pad_array(&a, &before, &after, &BorderMode::Valid), a);
+    }
+
+    #[test]
+    fn pad_2d_shape() {
+        let a = arr2(&[[1u8, 2], [3, 4]]);
+        let (before, after) = centred_padding(&Ix2(3, 2));
+        let p = pad_array(&a, &before, &after, &BorderMode::Nearest);
+        assert_eq!(p, arr2(&[[1, 1, 2], [1, 1, 2], [3, 3, 4], [3, 3, 4]]));
+    }
+}
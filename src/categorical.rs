@@ -0,0 +1,365 @@
+use ndarray::{Array, ArrayView, Axis, Dimension, IntoDimension, RemoveAxis, Slice};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_window_chunks;
+use crate::border_handling::{pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+
+/// which value wins when several values are equally common (or equally rare)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TieBreak {
+    Smallest,
+    Largest,
+    /// the value of the element the window is centred on (`window / 2` on each axis) when it is one
+    /// of the tied values, otherwise the smallest
+    KeepCentre,
+}
+
+/// types with at most this many values keep a count for every value while the window slides,
+/// `u8`, `i8`, `u16` and `i16`
+const DENSE_SPAN: usize = 1 << 16;
+/// types with at most this many values read the mode straight from the counts,
+/// larger types look up the count of each element in the window
+const SCAN_SPAN: usize = 256;
+
+/// majority (mode) filter, the most common value in the window centred on every element
+///
+/// `u8`, `i8`, `u16` and `i16` update a count of every value as the window slides along the last
+/// axis, larger types sort every window
+///
+/// ```
+/// use ndarray::{arr1, Ix1};
+/// let a = arr1(&[1u8, 1, 2, 2, 2, 3]);
+/// let m = majority_filter(a, Ix1(3), TieBreak::Smallest, BorderMode::Valid);
+/// assert_eq!(m, arr1(&[1, 2, 2, 2]));
+/// ```
+pub fn majority_filter<T, D>(arr: Array<T, D>, win_size: D, tie: TieBreak, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    rank_filter(arr, win_size, tie, true, border)
+}
+
+/// minority filter, the least common value present in the window centred on every element
+///
+/// see [majority_filter]
+pub fn minority_filter<T, D>(arr: Array<T, D>, win_size: D, tie: TieBreak, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    rank_filter(arr, win_size, tie, false, border)
+}
+
+/// variety filter, the number of distinct values in the window centred on every element
+///
+/// see [majority_filter]
+pub fn variety_filter<T, D>(arr: Array<T, D>, win_size: D, border: BorderMode<T>) -> Array<usize, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    count_windows(arr, win_size, border, |counts, _| counts.distinct(), |sorted, _| {
+        1 + sorted.windows(2).filter(|p| p[0].total_order(&p[1]).is_ne()).count()
+    })
+}
+
+fn rank_filter<T, D>(arr: Array<T, D>, win_size: D, tie: TieBreak, majority: bool, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    count_windows(
+        arr,
+        win_size,
+        border,
+        move |counts, w| {
+            let centre = centre_value(&w);
+            match counts.span <= SCAN_SPAN {
+                true => select(counts.present(), centre, tie, majority),
+                false => select(w.iter().map(|&x| (x, counts.count(x))), centre, tie, majority),
+            }
+        },
+        move |sorted, centre| select(runs(sorted), centre, tie, majority),
+    )
+}
+
+/// run `dense` with sliding counts for types with at most [DENSE_SPAN] values,
+/// otherwise `sorted` gets the sorted window and the centre value
+fn count_windows<T, O, D, F, G>(arr: Array<T, D>, win_size: D, border: BorderMode<T>, dense: F, sorted: G) -> Array<O, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(&WindowCounts<T>, ArrayView<T, D>) -> O + Send + Sync + 'static,
+    G: Fn(&[T], T) -> O + Send + Sync + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    match WindowCounts::<T>::span() <= DENSE_SPAN {
+        true => thread_over_window_chunks(arr, win_size, move |chunk, win_size| {
+            sliding_counts(chunk, win_size, &dense)
+        }),
+        false => thread_over_window_chunks(arr, win_size, move |chunk, win_size| {
+            let out_dim = chunk.raw_dim().size_sub_shape(&win_size);
+            let out: Vec<O> = chunk
+                .windows(win_size)
+                .into_iter()
+                .map(|w| {
+                    let mut values: Vec<T> = w.iter().copied().collect();
+                    values.sort_unstable_by(|a, b| a.total_order(b));
+                    sorted(&values, centre_value(&w))
+                })
+                .collect();
+            Array::from_shape_vec(out_dim, out).unwrap()
+        }),
+    }
+}
+
+/// count of every value in the current window
+struct WindowCounts<T> {
+    counts: Vec<u32>,
+    distinct: usize,
+    span: usize,
+    min: T,
+}
+
+impl<T: WinNum + Copy> WindowCounts<T> {
+    /// number of values of `T`, saturating at [usize::MAX] for the 64 and 128 bit types
+    fn span() -> usize {
+        ((T::MAX.as_f64() - T::MIN.as_f64()) as usize).saturating_add(1)
+    }
+
+    fn new() -> Self {
+        let span = Self::span();
+        WindowCounts { counts: vec![0; span], distinct: 0, span, min: T::MIN }
+    }
+
+    #[inline]
+    fn index(&self, x: T) -> usize {
+        (x.as_f64() - self.min.as_f64()) as usize
+    }
+
+    #[inline]
+    fn count(&self, x: T) -> u32 {
+        self.counts[self.index(x)]
+    }
+
+    fn distinct(&self) -> usize {
+        self.distinct
+    }
+
+    fn add<'a, I: IntoIterator<Item = &'a T>>(&mut self, values: I)
+    where
+        T: 'a,
+    {
+        for &x in values {
+            let i = self.index(x);
+            self.distinct += (self.counts[i] == 0) as usize;
+            self.counts[i] += 1;
+        }
+    }
+
+    fn remove<'a, I: IntoIterator<Item = &'a T>>(&mut self, values: I)
+    where
+        T: 'a,
+    {
+        for &x in values {
+            let i = self.index(x);
+            self.counts[i] -= 1;
+            self.distinct -= (self.counts[i] == 0) as usize;
+        }
+    }
+
+    /// values in the window with their counts, in ascending order
+    fn present(&self) -> impl Iterator<Item = (T, u32)> + '_ {
+        let min = self.min.as_f64();
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c > 0)
+            .map(move |(i, &c)| (T::from_f64(i as f64 + min), c))
+    }
+}
+
+/// valid window output of `arr`, the counts are updated one slab at a time as the window moves
+/// along the last axis
+fn sliding_counts<T, O, D, F>(arr: Array<T, D>, win_size: D, query: &F) -> Array<O, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy,
+    F: Fn(&WindowCounts<T>, ArrayView<T, D>) -> O,
+{
+    let out_dim = arr.raw_dim().size_sub_shape(&win_size);
+    let last = arr.ndim() - 1;
+    let win_last = win_size[last];
+    let mut lane_starts = out_dim;
+    lane_starts[last] = (out_dim[last] > 0) as usize;
+
+    let mut counts = WindowCounts::new();
+    let mut out = Vec::with_capacity(out_dim.size());
+    for start in ndarray::indices(lane_starts) {
+        let start: D = start.into_dimension();
+        let band = arr.slice_each_axis(|ad| match ad.axis.index() == last {
+            true => Slice::from(..),
+            false => {
+                let ax = ad.axis.index();
+                Slice::from(start[ax]..start[ax] + win_size[ax])
+            }
+        });
+        for k in 0..win_last {
+            counts.add(band.index_axis(Axis(last), k));
+        }
+        for j in 0..out_dim[last] {
+            if j > 0 {
+                counts.remove(band.index_axis(Axis(last), j - 1));
+                counts.add(band.index_axis(Axis(last), j - 1 + win_last));
+            }
+            out.push(query(&counts, band.slice_axis(Axis(last), Slice::from(j..j + win_last))));
+        }
+        // empty the counts again by removing the last window
+        for k in out_dim[last] - 1..out_dim[last] - 1 + win_last {
+            counts.remove(band.index_axis(Axis(last), k));
+        }
+    }
+    Array::from_shape_vec(out_dim, out).unwrap()
+}
+
+fn centre_value<T: Copy, D: Dimension>(w: &ArrayView<T, D>) -> T {
+    let mut centre = w.raw_dim();
+    for ax in 0..centre.ndim() {
+        centre[ax] /= 2;
+    }
+    w[centre]
+}
+
+/// values of an ascending slice with the number of times each one appears
+fn runs<T: WinNum + Copy>(sorted: &[T]) -> impl Iterator<Item = (T, u32)> + '_ {
+    sorted
+        .chunk_by(|a, b| a.total_order(b).is_eq())
+        .map(|run| (run[0], run.len() as u32))
+}
+
+/// most common (`majority`) or least common value of `(value, count)` pairs,
+/// pairs may repeat
+fn select<T, I>(pairs: I, centre: T, tie: TieBreak, majority: bool) -> T
+where
+    T: WinNum + Copy,
+    I: Iterator<Item = (T, u32)>,
+{
+    let mut centre_count = 0;
+    let (best, best_count) = pairs
+        .inspect(|&(v, c)| {
+            if v.total_order(&centre).is_eq() {
+                centre_count = c;
+            }
+        })
+        .reduce(|(bv, bc), (v, c)| {
+            let better = match (c == bc, majority) {
+                (false, true) => c > bc,
+                (false, false) => c < bc,
+                (true, _) => match tie {
+                    TieBreak::Largest => v.total_order(&bv).is_gt(),
+                    _ => v.total_order(&bv).is_lt(),
+                },
+            };
+            if better { (v, c) } else { (bv, bc) }
+        })
+        .unwrap();
+    match tie {
+        TieBreak::KeepCentre if centre_count == best_count => centre,
+        _ => best,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ndarray::{arr2, Array2, Array3, Ix2, Ix3};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    fn classes(ix: (usize, usize, usize)) -> u8 {
+        not_a_hash(ix) % 5
+    }
+
+    fn brute_majority(a: &Array3<u8>, win: Ix3, majority: bool) -> Array3<u8> {
+        let out: Vec<u8> = a
+            .windows(win)
+            .into_iter()
+            .map(|w| {
+                let mut counts = BTreeMap::new();
+                w.iter().for_each(|&x| *counts.entry(x).or_insert(0) += 1);
+                let best = match majority {
+                    true => counts.values().max(),
+                    false => counts.values().min(),
+                };
+                // BTreeMap iterates in ascending order, so this is the smallest tied value
+                *counts.iter().find(|(_, c)| Some(*c) == best).unwrap().0
+            })
+            .collect();
+        Array::from_shape_vec(a.raw_dim().size_sub_shape(&win), out).unwrap()
+    }
+
+    #[test]
+    fn sliding_counts_match_brute_force() {
+        let a: Array3<u8> = Array3::from_shape_fn((17, 12, 9), classes);
+        let win = Ix3(3, 2, 4);
+        let expected = brute_majority(&a, win, true);
+        assert_eq!(majority_filter(a.clone(), win, TieBreak::Smallest, BorderMode::Valid), expected);
+        // i16 looks up each element, u32 sorts every window
+        let signed = majority_filter(a.mapv(|x| x as i16 - 2), win, TieBreak::Smallest, BorderMode::Valid);
+        assert_eq!(signed.mapv(|x| (x + 2) as u8), expected);
+        let wide = majority_filter(a.mapv(|x| x as u32), win, TieBreak::Smallest, BorderMode::Valid);
+        assert_eq!(wide.mapv(|x| x as u8), expected);
+
+        let minority = brute_majority(&a, win, false);
+        assert_eq!(minority_filter(a.clone(), win, TieBreak::Smallest, BorderMode::Valid), minority);
+        let wide = minority_filter(a.mapv(|x| x as u32), win, TieBreak::Smallest, BorderMode::Valid);
+        assert_eq!(wide.mapv(|x| x as u8), minority);
+    }
+
+    #[test]
+    fn variety_counts_distinct() {
+        let a: Array3<u8> = Array3::from_shape_fn((11, 10, 3), classes);
+        let win = Ix3(4, 3, 2);
+        let expected: Vec<usize> = a
+            .windows(win)
+            .into_iter()
+            .map(|w| w.iter().collect::<std::collections::BTreeSet<_>>().len())
+            .collect();
+        let variety = variety_filter(a.clone(), win, BorderMode::Valid);
+        assert_eq!(variety.into_raw_vec(), expected);
+        let wide = variety_filter(a.mapv(|x| x as i32), win, BorderMode::Valid);
+        assert_eq!(wide.into_raw_vec(), expected);
+    }
+
+    #[test]
+    fn float_windows_are_sorted() {
+        let a: Array3<u8> = Array3::from_shape_fn((11, 10, 3), classes);
+        let win = Ix3(3, 3, 2);
+        let floats = a.mapv(|x| x as f64 * 0.5);
+        let mode = majority_filter(floats.clone(), win, TieBreak::Smallest, BorderMode::Valid);
+        assert_eq!(mode, brute_majority(&a, win, true).mapv(|x| x as f64 * 0.5));
+        let variety = variety_filter(floats, win, BorderMode::Valid);
+        assert_eq!(variety, variety_filter(a, win, BorderMode::Valid));
+    }
+
+    #[test]
+    fn tie_breaking() {
+        let a = arr2(&[[1u16, 1, 4], [4, 3, 3], [2, 2, 0]]);
+        let win = Ix2(3, 3);
+        let mode = |tie| majority_filter(a.clone(), win, tie, BorderMode::Valid)[[0, 0]];
+        assert_eq!(mode(TieBreak::Smallest), 1);
+        assert_eq!(mode(TieBreak::Largest), 4);
+        assert_eq!(mode(TieBreak::KeepCentre), 3);
+        assert_eq!(minority_filter(a.clone(), win, TieBreak::Largest, BorderMode::Valid)[[0, 0]], 0);
+
+        let b: Array2<u8> = arr2(&[[5, 5, 7], [7, 6, 6], [8, 8, 9]]);
+        // the centre 6 is tied with 5, 7 and 8
+        assert_eq!(majority_filter(b, win, TieBreak::KeepCentre, BorderMode::Nearest)[[1, 1]], 6);
+    }
+}
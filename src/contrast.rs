@@ -0,0 +1,87 @@
+use ndarray::{Array, Dimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+use crate::window_functions::{
+    window_mean_population_std, window_michelson_contrast, window_weber_contrast,
+};
+
+/// contrast of a window, calculated by [contrast_map]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContrastMeasure {
+    /// population standard deviation of the window, in the units of the input
+    Rms,
+    /// `(max - min) / (max + min)`, see [window_michelson_contrast]
+    Michelson,
+    /// `(centre - background) / background`, see [window_weber_contrast]
+    Weber,
+}
+
+/// contrast of the window centred on every element as [f64]
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1`
+pub fn contrast_map<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    measure: ContrastMeasure,
+    border: BorderMode<T>,
+) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    match measure {
+        ContrastMeasure::Rms => {
+            thread_over_any_window_map(arr, win_size, |w| window_mean_population_std(w).1)
+        }
+        ContrastMeasure::Michelson => thread_over_any_window_map(arr, win_size, window_michelson_contrast),
+        ContrastMeasure::Weber => thread_over_any_window_map(arr, win_size, window_weber_contrast),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array2, Ix1, Ix2};
+
+    use crate::array_threading::apply_over_any_window;
+    use crate::window_functions::func_area_contrast;
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn rms_matches_reference() {
+        let a: Array2<u8> = Array2::from_shape_fn((14, 11), not_a_hash);
+        let rms = contrast_map(a.clone(), Ix2(3, 4), ContrastMeasure::Rms, BorderMode::Valid);
+        for (r, w) in rms.iter().zip(a.windows(Ix2(3, 4))) {
+            let w = w.mapv(|x| x as f64);
+            let mean = w.sum() / w.len() as f64;
+            let reference = (w.mapv(|x| (x - mean).powi(2)).sum() / w.len() as f64).sqrt();
+            assert!((r - reference).abs() < 1e-9);
+        }
+        let rounded = apply_over_any_window(a, Ix2(3, 4), func_area_contrast);
+        assert_eq!(rms.mapv(u8::from_f64), rounded);
+    }
+
+    #[test]
+    fn michelson_and_weber() {
+        let a = arr1(&[10u8, 30, 20, 0, 0, 0]);
+        let m = contrast_map(a.clone(), Ix1(3), ContrastMeasure::Michelson, BorderMode::Valid);
+        assert_eq!(m, arr1(&[0.5, 1.0, 1.0, 0.0]));
+        let w = contrast_map(a, Ix1(3), ContrastMeasure::Weber, BorderMode::Valid);
+        // centre 30 on a background of 15, centre 20 on 15, centre 0 on 10, flat zeros
+        assert_eq!(w, arr1(&[1.0, 5.0 / 15.0, -1.0, 0.0]));
+    }
+
+    #[test]
+    fn keeps_shape_with_padding() {
+        let a = arr2(&[[1u16, 2, 3], [4, 5, 6]]);
+        let m = contrast_map(a, Ix2(3, 3), ContrastMeasure::Michelson, BorderMode::Nearest);
+        assert_eq!(m.shape(), &[2, 3]);
+        assert!((m[[0, 0]] - 4.0 / 6.0).abs() < 1e-12);
+    }
+}
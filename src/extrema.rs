@@ -0,0 +1,116 @@
+use ndarray::{Array, Axis, Dimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+use crate::window_functions::{window_max_offset, window_min_offset};
+
+/// which extreme value of a window to locate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Extremum {
+    Max,
+    Min,
+}
+
+/// extreme value of every window and where it is, as the offset in logical (row major) order
+/// from the first element of the window
+///
+/// ties go to the first element in logical order, so the result never depends on the thread count,
+/// with any border except [BorderMode::Valid] the window is centred on each element,
+/// so the element itself is at offset `window / 2` on each axis
+///
+/// ```
+/// use ndarray::{arr1, Ix1};
+/// let a = arr1(&[3u8, 1, 4, 1, 5]);
+/// let (values, offsets) = extremum_offset_map(a, Ix1(3), Extremum::Min, BorderMode::Valid);
+/// assert_eq!(values, arr1(&[1, 1, 1]));
+/// assert_eq!(offsets, arr1(&[1, 0, 1]));
+/// ```
+pub fn extremum_offset_map<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    extremum: Extremum,
+    border: BorderMode<T>,
+) -> (Array<T, D>, Array<usize, D>)
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    let pairs = match extremum {
+        Extremum::Max => thread_over_any_window_map(arr, win_size, window_max_offset),
+        Extremum::Min => thread_over_any_window_map(arr, win_size, window_min_offset),
+    };
+    (pairs.mapv(|(v, _)| v), pairs.mapv(|(_, o)| o))
+}
+
+/// split offsets from [extremum_offset_map] into one offset per window axis,
+/// placed on a new last axis with length `win_size.ndim()`
+///
+/// ```
+/// use ndarray::{arr2, Ix2};
+/// let offsets = arr2(&[[5usize]]);
+/// let per_axis = unravel_offsets(&offsets, Ix2(2, 3));
+/// assert_eq!(per_axis.into_raw_vec(), vec![1, 2]);
+/// ```
+pub fn unravel_offsets<D>(offsets: &Array<usize, D>, win_size: D) -> Array<usize, D::Larger>
+where
+    D: Dimension,
+{
+    let ndim = win_size.ndim();
+    let last = offsets.ndim();
+    let mut shape = offsets.raw_dim().insert_axis(Axis(last));
+    shape[last] = ndim;
+    let mut flat = Vec::with_capacity(offsets.len() * ndim);
+    for &o in offsets.iter() {
+        let start = flat.len();
+        let mut rem = o;
+        for ax in (0..ndim).rev() {
+            flat.push(rem % win_size[ax]);
+            rem /= win_size[ax];
+        }
+        flat[start..].reverse();
+    }
+    Array::from_shape_vec(shape, flat).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr2, Array2, Ix2};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn offsets_point_at_values() {
+        let a: Array2<u8> = Array2::from_shape_fn((23, 17), not_a_hash);
+        let win = Ix2(4, 3);
+        let (values, offsets) = extremum_offset_map(a.clone(), win, Extremum::Max, BorderMode::Valid);
+        let per_axis = unravel_offsets(&offsets, win);
+        for (((r, c), &v), w) in values.indexed_iter().zip(a.windows(win)) {
+            let (dr, dc) = (per_axis[[r, c, 0]], per_axis[[r, c, 1]]);
+            assert_eq!(a[[r + dr, c + dc]], v);
+            assert_eq!(w.iter().position(|&x| x == v), Some(offsets[[r, c]]));
+        }
+    }
+
+    #[test]
+    fn ties_go_to_the_first() {
+        let a = arr2(&[[2u8, 7, 7], [7, 0, 0], [0, 0, 0]]);
+        let (_, max) = extremum_offset_map(a.clone(), Ix2(3, 3), Extremum::Max, BorderMode::Valid);
+        assert_eq!(max[[0, 0]], 1);
+        let (_, min) = extremum_offset_map(a, Ix2(3, 3), Extremum::Min, BorderMode::Valid);
+        assert_eq!(min[[0, 0]], 4);
+    }
+
+    #[test]
+    fn centred_with_border() {
+        let a = arr2(&[[1u8, 2], [3, 4]]);
+        let (values, offsets) = extremum_offset_map(a, Ix2(3, 3), Extremum::Max, BorderMode::Constant(0));
+        assert_eq!(values, arr2(&[[4, 4], [4, 4]]));
+        // the 4 is below and to the right of the centre in the first window
+        assert_eq!(offsets, arr2(&[[8, 7], [5, 4]]));
+    }
+}
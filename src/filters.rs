@@ -0,0 +1,742 @@
+use ndarray::{Array, ArrayView, Dimension, IntoDimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{centre_crop, centred_padding, pad_array, BorderMode};
+use crate::integer_conversion_traits::{ConversionError, ConversionPolicy, FilterOutput, WinNum};
+
+/// default `truncate` used by [gaussian_filter], the same default as `scipy.ndimage.gaussian_filter`
+pub const GAUSSIAN_TRUNCATE: f64 = 4.0;
+
+/// normalised 1d gaussian kernel with a radius of `truncate * sigma` (rounded to nearest)
+///
+/// a `sigma` of zero returns `[1.0]`, which leaves the axis unchanged
+///
+/// ```
+/// let k = gaussian_kernel_1d(1.0, 4.0);
+/// assert_eq!(k.len(), 9);
+/// assert!((k.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+/// ```
+pub fn gaussian_kernel_1d(sigma: f64, truncate: f64) -> Vec<f64> {
+    if sigma <= 0f64 {
+        return vec![1f64];
+    }
+    let radius = (truncate * sigma + 0.5) as isize;
+    let inv_2s2 = (2f64 * sigma * sigma).recip();
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|x| (-((x * x) as f64) * inv_2s2).exp())
+        .collect();
+    let total: f64 = kernel.iter().sum();
+    kernel.into_iter().map(|k| k / total).collect()
+}
+
+/// correlate every line along `axis` with a 1d `kernel`, all other axes are untouched
+///
+/// the kernel is centred on `kernel.len() / 2`, with [BorderMode::Valid] `axis` shrinks
+/// by `kernel.len() - 1`
+pub fn correlate_axis<D>(
+    arr: Array<f64, D>,
+    axis: usize,
+    kernel: &[f64],
+    border: BorderMode<f64>,
+) -> Array<f64, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let mut win_size = arr.raw_dim();
+    for ax in 0..win_size.ndim() {
+        win_size[ax] = 1;
+    }
+    win_size[axis] = kernel.len();
+
+    let arr = match border {
+        BorderMode::Valid => arr,
+        _ => {
+            let (before, after) = centred_padding(&win_size);
+            pad_array(&arr, &before, &after, &border)
+        }
+    };
+    let kernel = kernel.to_vec();
+    thread_over_any_window_map(arr, win_size, move |w: ArrayView<f64, D>| {
+        w.iter().zip(kernel.iter()).map(|(x, k)| x * k).sum::<f64>()
+    })
+}
+
+/// apply one 1d kernel per axis, `kernels` shorter than the number of dimensions leave the
+/// remaining axes unchanged
+pub fn correlate_separable<D>(
+    arr: Array<f64, D>,
+    kernels: &[Vec<f64>],
+    border: BorderMode<f64>,
+) -> Array<f64, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let ndim = arr.ndim();
+    kernels
+        .iter()
+        .take(ndim)
+        .enumerate()
+        .filter(|(_, k)| k.len() > 1)
+        .fold(arr, |a, (axis, k)| correlate_axis(a, axis, k, border))
+}
+
+/// gaussian smoothing with the result kept as [f64], see [gaussian_filter]
+pub fn gaussian_filter_f64<T, D>(
+    arr: Array<T, D>,
+    sigma: &[f64],
+    truncate: f64,
+    border: BorderMode<T>,
+) -> Array<f64, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let kernels: Vec<Vec<f64>> = sigma
+        .iter()
+        .map(|&s| gaussian_kernel_1d(s, truncate))
+        .collect();
+    correlate_separable(
+        arr.mapv(|x| x.as_f64()),
+        &kernels,
+        border.map_constant(|k| k.as_f64()),
+    )
+}
+
+/// separable gaussian blur with a `sigma` per axis
+///
+/// - `sigma` shorter than the number of dimensions will leave the remaining axes unblurred
+/// - `truncate` sets the kernel radius in standard deviations, see [GAUSSIAN_TRUNCATE]
+/// - intermediate passes are kept as [f64], the result is rounded back into `T` once at the end
+/// - with [BorderMode::Valid] each axis shrinks by `2 * radius`
+///
+/// ```
+/// use ndarray::Array2;
+/// let a: Array2<u8> = Array2::from_elem((20, 20), 100);
+/// let b = gaussian_filter(a, &[2.0, 2.0], GAUSSIAN_TRUNCATE, BorderMode::Reflect);
+/// assert!(b.iter().all(|&x| x == 100));
+/// ```
+pub fn gaussian_filter<T, D>(
+    arr: Array<T, D>,
+    sigma: &[f64],
+    truncate: f64,
+    border: BorderMode<T>,
+) -> Array<T, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    gaussian_filter_f64(arr, sigma, truncate, border).mapv(T::from_f64)
+}
+
+/// [gaussian_filter] with the result written into `T` with `policy` instead of rounding half
+/// away from zero and saturating, the first result which does not fit is returned as the error
+/// with [Overflow::Error](crate::integer_conversion_traits::Overflow::Error)
+pub fn gaussian_filter_converted<T, D>(
+    arr: Array<T, D>,
+    sigma: &[f64],
+    truncate: f64,
+    border: BorderMode<T>,
+    policy: ConversionPolicy,
+) -> Result<Array<T, D>, ConversionError>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    convert_with(gaussian_filter_f64(arr, sigma, truncate, border), policy)
+}
+
+/// every element of `arr` converted with `policy`, stops at the first error
+fn convert_with<T, D>(arr: Array<f64, D>, policy: ConversionPolicy) -> Result<Array<T, D>, ConversionError>
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let out = arr.iter().map(|&x| policy.convert(x)).collect::<Result<Vec<T>, _>>()?;
+    Ok(Array::from_shape_vec(arr.raw_dim(), out).unwrap())
+}
+
+/// 3 element derivative operators, a central difference along the derivative axis
+/// and a smoothing kernel along every other axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientOperator {
+    /// smoothing `[1, 2, 1]`
+    Sobel,
+    /// smoothing `[1, 1, 1]`
+    Prewitt,
+    /// smoothing `[3, 10, 3]`, better rotational symmetry than [GradientOperator::Sobel]
+    Scharr,
+}
+
+impl GradientOperator {
+    /// unnormalised smoothing kernel used on the axes which are not differentiated
+    pub fn smoothing_kernel(&self) -> Vec<f64> {
+        match self {
+            GradientOperator::Sobel => vec![1f64, 2f64, 1f64],
+            GradientOperator::Prewitt => vec![1f64, 1f64, 1f64],
+            GradientOperator::Scharr => vec![3f64, 10f64, 3f64],
+        }
+    }
+    /// central difference, positive where values increase along the axis
+    pub fn derivative_kernel(&self) -> Vec<f64> {
+        vec![-1f64, 0f64, 1f64]
+    }
+}
+
+/// derivative along `axis` of an array already converted to [f64]
+fn gradient_axis_f64<D>(
+    arr: Array<f64, D>,
+    axis: usize,
+    operator: GradientOperator,
+    border: BorderMode<f64>,
+) -> Array<f64, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let kernels: Vec<Vec<f64>> = (0..arr.ndim())
+        .map(|ax| match ax == axis {
+            true => operator.derivative_kernel(),
+            false => operator.smoothing_kernel(),
+        })
+        .collect();
+    correlate_separable(arr, &kernels, border)
+}
+
+/// Sobel / Prewitt / Scharr derivative along `axis` of an N dimensional array
+///
+/// the sum is done in [f64], so unsigned inputs can't wrap, the result is then written into `O`,
+/// a signed integer ([i16] is enough for [u8] inputs) or a float
+///
+/// ```
+/// use ndarray::arr2;
+/// let a = arr2(&[[0u8, 0, 10, 10], [0, 0, 10, 10], [0, 0, 10, 10]]);
+/// let gx = gradient_axis::<u8, i16, _>(a, 1, GradientOperator::Sobel, BorderMode::Valid);
+/// assert_eq!(gx, arr2(&[[40i16, 40]]));
+/// ```
+///
+/// # panics
+/// if `axis` is not smaller than the number of dimensions
+pub fn gradient_axis<T, O, D>(
+    arr: Array<T, D>,
+    axis: usize,
+    operator: GradientOperator,
+    border: BorderMode<T>,
+) -> Array<O, D>
+where
+    T: WinNum + Copy,
+    O: FilterOutput,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    assert!(axis < arr.ndim(), "axis {} out of range", axis);
+    gradient_axis_f64(
+        arr.mapv(|x| x.as_f64()),
+        axis,
+        operator,
+        border.map_constant(|k| k.as_f64()),
+    )
+    .mapv(O::from_filter_f64)
+}
+
+/// Euclidean norm of the [gradient_axis] results over every axis
+pub fn gradient_magnitude<T, O, D>(
+    arr: Array<T, D>,
+    operator: GradientOperator,
+    border: BorderMode<T>,
+) -> Array<O, D>
+where
+    T: WinNum + Copy,
+    O: FilterOutput,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = arr.mapv(|x| x.as_f64());
+    let border = border.map_constant(|k| k.as_f64());
+    let mut total: Option<Array<f64, D>> = None;
+    for axis in 0..arr.ndim() {
+        let g = gradient_axis_f64(arr.clone(), axis, operator, border);
+        total = Some(match total {
+            None => g.mapv(|x| x * x),
+            Some(mut t) => {
+                t.zip_mut_with(&g, |t, &g| *t += g * g);
+                t
+            }
+        });
+    }
+    total.unwrap().mapv(|x| O::from_filter_f64(x.sqrt()))
+}
+
+/// second derivative of [gaussian_kernel_1d], a `sigma` of zero returns `[1, -2, 1]`
+///
+/// the truncated kernel is shifted so it sums to exactly zero, flat regions then give zero
+pub fn gaussian_kernel_1d_order2(sigma: f64, truncate: f64) -> Vec<f64> {
+    if sigma <= 0f64 {
+        return vec![1f64, -2f64, 1f64];
+    }
+    let phi = gaussian_kernel_1d(sigma, truncate);
+    let radius = (phi.len() / 2) as f64;
+    let (s2, s4) = (sigma.powi(2), sigma.powi(4));
+    let kernel: Vec<f64> = phi
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let x = i as f64 - radius;
+            p * (x * x - s2) / s4
+        })
+        .collect();
+    let mean = kernel.iter().sum::<f64>() / kernel.len() as f64;
+    kernel.into_iter().map(|k| k - mean).collect()
+}
+
+/// sum over every axis of `kernels_for(axis)` applied separably to `arr`
+///
+/// with [BorderMode::Valid] each part can shrink by a different amount,
+/// they are cropped around the centre to the smallest shape before adding
+fn sum_of_axis_filters<D, F>(arr: Array<f64, D>, border: BorderMode<f64>, kernels_for: F) -> Array<f64, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(usize) -> Vec<Vec<f64>>,
+{
+    let parts: Vec<Array<f64, D>> = (0..arr.ndim())
+        .map(|axis| correlate_separable(arr.clone(), &kernels_for(axis), border))
+        .collect();
+    let mut shape = parts[0].raw_dim();
+    for part in parts.iter() {
+        for ax in 0..shape.ndim() {
+            shape[ax] = shape[ax].min(part.raw_dim()[ax]);
+        }
+    }
+    parts
+        .iter()
+        .map(|part| centre_crop(part, &shape))
+        .reduce(|a, b| a + b)
+        .unwrap()
+}
+
+/// N dimensional discrete Laplacian, the sum of `[1, -2, 1]` along every axis
+///
+/// flat regions are zero, so `O` should be signed or a float
+///
+/// ```
+/// use ndarray::arr1;
+/// let l = laplace::<u8, i16, _>(arr1(&[0u8, 0, 10, 0, 0]), BorderMode::Valid);
+/// assert_eq!(l, arr1(&[10i16, -20, 10]));
+/// ```
+pub fn laplace<T, O, D>(arr: Array<T, D>, border: BorderMode<T>) -> Array<O, D>
+where
+    T: WinNum + Copy,
+    O: FilterOutput,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    laplace_f64(arr, border).mapv(O::from_filter_f64)
+}
+
+fn laplace_f64<T, D>(arr: Array<T, D>, border: BorderMode<T>) -> Array<f64, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let ndim = arr.ndim();
+    sum_of_axis_filters(
+        arr.mapv(|x| x.as_f64()),
+        border.map_constant(|k| k.as_f64()),
+        |axis| {
+            (0..ndim)
+                .map(|ax| match ax == axis {
+                    true => vec![1f64, -2f64, 1f64],
+                    false => vec![1f64],
+                })
+                .collect()
+        },
+    )
+}
+
+/// Laplacian of Gaussian, [gaussian_kernel_1d_order2] along each axis in turn with
+/// [gaussian_kernel_1d] along the others, summed over every axis
+///
+/// `sigma` shorter than the number of dimensions is padded with zeros
+pub fn gaussian_laplace<T, O, D>(
+    arr: Array<T, D>,
+    sigma: &[f64],
+    truncate: f64,
+    border: BorderMode<T>,
+) -> Array<O, D>
+where
+    T: WinNum + Copy,
+    O: FilterOutput,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let ndim = arr.ndim();
+    let sigma: Vec<f64> = (0..ndim)
+        .map(|ax| sigma.get(ax).copied().unwrap_or(0f64))
+        .collect();
+    sum_of_axis_filters(
+        arr.mapv(|x| x.as_f64()),
+        border.map_constant(|k| k.as_f64()),
+        |axis| {
+            (0..ndim)
+                .map(|ax| match ax == axis {
+                    true => gaussian_kernel_1d_order2(sigma[ax], truncate),
+                    false => gaussian_kernel_1d(sigma[ax], truncate),
+                })
+                .collect()
+        },
+    )
+    .mapv(O::from_filter_f64)
+}
+
+/// population variance of a window of [f64] values
+#[inline]
+fn window_variance<D: Dimension>(w: ArrayView<f64, D>) -> f64 {
+    let len_inv = (w.len() as f64).recip();
+    let mean = w.sum() * len_inv;
+    w.fold(0f64, |a, x| a + (x - mean).powi(2)) * len_inv
+}
+
+/// variance of the [laplace] result over the whole array, a single focus score,
+/// sharper images have a higher score
+pub fn laplacian_variance<T, D>(arr: Array<T, D>, border: BorderMode<T>) -> f64
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    window_variance(laplace_f64(arr, border).view())
+}
+
+/// variance of the [laplace] result over each window, a local focus map
+///
+/// `border` is used for the Laplacian and again for the variance window, a
+/// [BorderMode::Constant] pads the Laplacian with zeros
+pub fn laplacian_variance_map<T, D>(arr: Array<T, D>, win_size: D, border: BorderMode<T>) -> Array<f64, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let lap = laplace_f64(arr, border);
+    let lap = match border {
+        BorderMode::Valid => lap,
+        _ => {
+            let (before, after) = centred_padding(&win_size);
+            pad_array(&lap, &before, &after, &border.map_constant(|_| 0f64))
+        }
+    };
+    thread_over_any_window_map(lap, win_size, window_variance)
+}
+
+/// `true` for a sigma [bilateral_filter] can use, positive and finite
+pub fn valid_sigma(sigma: f64) -> bool {
+    sigma > 0f64 && sigma.is_finite()
+}
+
+/// range weights for [bilateral_filter], a lookup table indexed by `|x - centre|`
+/// for 8 bit inputs, calculated directly for everything else
+enum RangeWeights {
+    Table(Vec<f64>),
+    Direct(f64),
+}
+
+impl RangeWeights {
+    fn new<T: WinNum>(sigma_range: f64) -> Self {
+        let inv_2s2 = (2f64 * sigma_range * sigma_range).recip();
+        let span = T::MAX.as_f64() - T::MIN.as_f64();
+        match span <= u8::MAX as f64 {
+            true => RangeWeights::Table(
+                (0..=span as usize)
+                    .map(|d| (-((d * d) as f64) * inv_2s2).exp())
+                    .collect(),
+            ),
+            false => RangeWeights::Direct(inv_2s2),
+        }
+    }
+    #[inline]
+    fn weight(&self, diff: f64) -> f64 {
+        match self {
+            RangeWeights::Table(t) => t[diff.abs() as usize],
+            RangeWeights::Direct(inv_2s2) => (-diff * diff * inv_2s2).exp(),
+        }
+    }
+}
+
+/// edge preserving smoothing, each element is the average of its window weighted by
+/// distance from the centre (`sigma_spatial`) and by difference from the centre value (`sigma_range`)
+///
+/// - the window is centred on `win_size / 2`, odd window shapes are recommended
+/// - [u8] and [i8] inputs use a lookup table for the range weights
+/// - the result is rounded back into `T`, see [bilateral_filter_converted] for other conversions
+///
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let a: Array2<u8> = Array2::from_elem((10, 10), 7);
+/// let b = bilateral_filter(a.clone(), Ix2(5, 5), 2.0, 20.0, BorderMode::Reflect);
+/// assert_eq!(a, b);
+/// ```
+///
+/// # panics
+/// if either sigma is not positive and finite, a zero sigma gives `NaN` weights
+pub fn bilateral_filter<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    sigma_spatial: f64,
+    sigma_range: f64,
+    border: BorderMode<T>,
+) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    bilateral_filter_f64(arr, win_size, sigma_spatial, sigma_range, border).mapv(T::from_f64)
+}
+
+/// [bilateral_filter] with the result written into `T` with `policy`, the first result which
+/// does not fit is returned as the error with [Overflow::Error](crate::integer_conversion_traits::Overflow::Error)
+///
+/// # panics
+/// if either sigma is not positive and finite
+pub fn bilateral_filter_converted<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    sigma_spatial: f64,
+    sigma_range: f64,
+    border: BorderMode<T>,
+    policy: ConversionPolicy,
+) -> Result<Array<T, D>, ConversionError>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    convert_with(bilateral_filter_f64(arr, win_size, sigma_spatial, sigma_range, border), policy)
+}
+
+/// edge preserving smoothing with the result kept as [f64], see [bilateral_filter]
+///
+/// # panics
+/// if either sigma is not positive and finite
+pub fn bilateral_filter_f64<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    sigma_spatial: f64,
+    sigma_range: f64,
+    border: BorderMode<T>,
+) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    assert!(valid_sigma(sigma_spatial), "sigma_spatial must be positive and finite, got {}", sigma_spatial);
+    assert!(valid_sigma(sigma_range), "sigma_range must be positive and finite, got {}", sigma_range);
+    let (centre, after) = centred_padding(&win_size);
+    let inv_2s2 = (2f64 * sigma_spatial * sigma_spatial).recip();
+    let spatial: Array<f64, D> = Array::from_shape_fn(win_size, |pattern| {
+        let ix = pattern.into_dimension();
+        let d2: f64 = (0..ix.ndim())
+            .map(|ax| (ix[ax] as f64 - centre[ax] as f64).powi(2))
+            .sum();
+        (-d2 * inv_2s2).exp()
+    });
+    let range = RangeWeights::new::<T>(sigma_range);
+
+    let arr = match border {
+        BorderMode::Valid => arr,
+        _ => pad_array(&arr, &centre, &after, &border),
+    };
+    thread_over_any_window_map(arr, win_size, move |w: ArrayView<T, D>| {
+        let c = w[centre].as_f64();
+        let (total, norm) = w.iter().zip(spatial.iter()).fold((0f64, 0f64), |(t, n), (x, s)| {
+            let x = x.as_f64();
+            let weight = s * range.weight(x - c);
+            (t + weight * x, n + weight)
+        });
+        total / norm
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, Array1, Array2};
+
+    use crate::integer_conversion_traits::{Overflow, Rounding};
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn kernel_shape() {
+        let k = gaussian_kernel_1d(1.5, 4.0);
+        assert_eq!(k.len(), 13);
+        assert!((k.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        // symmetric and peaked in the middle
+        assert_eq!(k[0], k[12]);
+        assert!(k.iter().all(|&x| x <= k[6]));
+        assert_eq!(gaussian_kernel_1d(0.0, 4.0), vec![1.0]);
+    }
+
+    #[test]
+    fn separable_matches_direct() {
+        let a: Array2<u8> = Array2::from_shape_fn((30, 25), not_a_hash);
+        let (kx, ky) = (gaussian_kernel_1d(1.0, 2.0), gaussian_kernel_1d(0.7, 2.0));
+        let out = gaussian_filter_f64(a.clone(), &[1.0, 0.7], 2.0, BorderMode::Valid);
+        assert_eq!(out.shape(), &[30 - 4, 25 - 2]);
+        for ((i, j), v) in out.indexed_iter() {
+            let mut direct = 0f64;
+            for (di, wx) in kx.iter().enumerate() {
+                for (dj, wy) in ky.iter().enumerate() {
+                    direct += wx * wy * a[[i + di, j + dj]] as f64;
+                }
+            }
+            assert!((direct - v).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rounds_back_into_input_type() {
+        let a: Array1<u8> = arr1(&[0, 0, 0, 255, 0, 0, 0]);
+        let out = gaussian_filter(a, &[1.0], GAUSSIAN_TRUNCATE, BorderMode::Constant(0));
+        let k = gaussian_kernel_1d(1.0, GAUSSIAN_TRUNCATE);
+        let expected: Vec<u8> = (0..7)
+            .map(|i| (255f64 * k[4 + i - 3]).round() as u8)
+            .collect();
+        assert_eq!(out.to_vec(), expected);
+        // the blur is symmetric, so the output is too
+        assert_eq!(out[0], out[6]);
+    }
+
+    #[test]
+    fn zero_sigma_is_identity() {
+        let a: Array2<u8> = Array2::from_shape_fn((10, 12), not_a_hash);
+        assert_eq!(gaussian_filter(a.clone(), &[0.0], 4.0, BorderMode::Nearest), a);
+    }
+
+    #[test]
+    fn sobel_does_not_wrap_unsigned() {
+        // a falling edge gives a negative derivative
+        let a = ndarray::arr2(&[[255u8, 255, 0], [255, 255, 0], [255, 255, 0]]);
+        let gx = gradient_axis::<u8, i16, _>(a.clone(), 1, GradientOperator::Sobel, BorderMode::Valid);
+        assert_eq!(gx, ndarray::arr2(&[[-1020i16]]));
+        let gy = gradient_axis::<u8, f64, _>(a, 0, GradientOperator::Sobel, BorderMode::Valid);
+        assert_eq!(gy, ndarray::arr2(&[[0f64]]));
+    }
+
+    #[test]
+    fn operators_match_direct_3x3() {
+        let a: Array2<u8> = Array2::from_shape_fn((12, 9), not_a_hash);
+        for op in [GradientOperator::Sobel, GradientOperator::Prewitt, GradientOperator::Scharr] {
+            let s = op.smoothing_kernel();
+            let gx = gradient_axis::<u8, i32, _>(a.clone(), 1, op, BorderMode::Valid);
+            for ((i, j), v) in gx.indexed_iter() {
+                let mut direct = 0f64;
+                for di in 0..3 {
+                    direct += s[di] * (a[[i + di, j + 2]] as f64 - a[[i + di, j]] as f64);
+                }
+                assert_eq!(*v, direct as i32);
+            }
+        }
+    }
+
+    #[test]
+    fn magnitude_3d() {
+        let a: ndarray::Array3<u16> =
+            ndarray::Array3::from_shape_fn((5, 6, 7), |(a, b, c)| (3 * a + 4 * b + 12 * c) as u16);
+        let m = gradient_magnitude::<u16, f64, _>(a, GradientOperator::Prewitt, BorderMode::Valid);
+        // linear ramp: each derivative is 2 * slope * 9 (the other two axes sum [1,1,1] twice)
+        assert!(m.iter().all(|&x| (x - 18f64 * 13f64).abs() < 1e-9));
+    }
+
+    #[test]
+    fn laplace_2d() {
+        let mut a: Array2<u8> = Array2::zeros((5, 5));
+        a[[2, 2]] = 8;
+        let l = laplace::<u8, i16, _>(a, BorderMode::Constant(0));
+        assert_eq!(l[[2, 2]], -32);
+        assert_eq!(l[[1, 2]], 8);
+        assert_eq!(l[[1, 1]], 0);
+        assert_eq!(l.sum(), 0);
+    }
+
+    #[test]
+    fn order2_kernel_sums_to_zero() {
+        let k = gaussian_kernel_1d_order2(2.0, 4.0);
+        assert_eq!(k.len(), gaussian_kernel_1d(2.0, 4.0).len());
+        assert!(k.iter().sum::<f64>().abs() < 1e-12);
+        // negative in the middle, like the continuous second derivative
+        assert!(k[k.len() / 2] < 0f64);
+    }
+
+    #[test]
+    fn log_of_flat_is_zero() {
+        let a: Array2<u16> = Array2::from_elem((16, 16), 300);
+        let l = gaussian_laplace::<u16, f64, _>(a, &[1.5, 1.5], 4.0, BorderMode::Nearest);
+        assert!(l.iter().all(|x| x.abs() < 1e-6));
+    }
+
+    #[test]
+    fn focus_score_drops_after_blur() {
+        let a: Array2<u8> = Array2::from_shape_fn((40, 40), not_a_hash);
+        let blurred = gaussian_filter(a.clone(), &[2.0, 2.0], 4.0, BorderMode::Reflect);
+        let sharp_score = laplacian_variance(a.clone(), BorderMode::Reflect);
+        let blur_score = laplacian_variance(blurred, BorderMode::Reflect);
+        assert!(sharp_score > blur_score);
+
+        let map = laplacian_variance_map(a.clone(), ndarray::Ix2(5, 5), BorderMode::Reflect);
+        assert_eq!(map.shape(), a.shape());
+        let valid = laplacian_variance_map(a, ndarray::Ix2(5, 5), BorderMode::Valid);
+        assert_eq!(valid.shape(), &[34, 34]);
+    }
+
+    #[test]
+    fn bilateral_keeps_edges() {
+        let a: Array2<u8> = Array2::from_shape_fn((12, 12), |(_, j)| if j < 6 { 20 } else { 200 });
+        let bil = bilateral_filter(a.clone(), ndarray::Ix2(5, 5), 2.0, 10.0, BorderMode::Reflect);
+        assert_eq!(bil, a);
+        let blur = gaussian_filter(a.clone(), &[1.0, 1.0], 2.0, BorderMode::Reflect);
+        assert_ne!(blur, a);
+    }
+
+    #[test]
+    fn bilateral_lookup_matches_direct() {
+        // u8 uses the lookup table, u16 calculates every weight
+        let a: Array2<u8> = Array2::from_shape_fn((20, 17), not_a_hash);
+        let b: Array2<u16> = a.mapv(|x| x as u16);
+        let win = ndarray::Ix2(3, 5);
+        let out_a = bilateral_filter(a, win, 1.5, 30.0, BorderMode::Nearest);
+        let out_b = bilateral_filter(b, win, 1.5, 30.0, BorderMode::Nearest);
+        assert_eq!(out_a.mapv(|x| x as u16), out_b);
+        assert_eq!(out_a.shape(), &[20, 17]);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigma_spatial must be positive")]
+    fn bilateral_rejects_zero_sigma_spatial() {
+        bilateral_filter(Array2::<u8>::zeros((5, 5)), ndarray::Ix2(3, 3), 0.0, 10.0, BorderMode::Reflect);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigma_range must be positive")]
+    fn bilateral_rejects_zero_sigma_range() {
+        bilateral_filter(Array2::<u8>::zeros((5, 5)), ndarray::Ix2(3, 3), 1.0, 0.0, BorderMode::Reflect);
+    }
+
+    #[test]
+    #[should_panic(expected = "sigma_range must be positive")]
+    fn bilateral_rejects_non_finite_sigma() {
+        bilateral_filter(Array2::<u8>::zeros((5, 5)), ndarray::Ix2(3, 3), 1.0, f64::NAN, BorderMode::Reflect);
+    }
+
+    #[test]
+    fn converted_filters() {
+        let a: Array2<u8> = Array2::from_shape_fn((12, 9), not_a_hash);
+        let win = ndarray::Ix2(3, 3);
+        let floor = ConversionPolicy { rounding: Rounding::Floor, overflow: Overflow::Saturate };
+        let blur = gaussian_filter_f64(a.clone(), &[1.0, 1.5], 3.0, BorderMode::Reflect);
+        let converted = gaussian_filter_converted(a.clone(), &[1.0, 1.5], 3.0, BorderMode::Reflect, floor);
+        assert_eq!(converted, Ok(blur.mapv(|x| x.floor() as u8)));
+        assert_eq!(
+            gaussian_filter_converted(a.clone(), &[1.0, 1.5], 3.0, BorderMode::Reflect, ConversionPolicy::DEFAULT),
+            Ok(gaussian_filter(a.clone(), &[1.0, 1.5], 3.0, BorderMode::Reflect))
+        );
+        let smooth = bilateral_filter_f64(a.clone(), win, 1.0, 40.0, BorderMode::Nearest);
+        let converted = bilateral_filter_converted(a.clone(), win, 1.0, 40.0, BorderMode::Nearest, ConversionPolicy::NUMPY);
+        assert_eq!(converted, Ok(smooth.mapv(|x| x.trunc() as u8)));
+        assert_eq!(
+            bilateral_filter_converted(a.clone(), win, 1.0, 40.0, BorderMode::Nearest, ConversionPolicy::DEFAULT),
+            Ok(bilateral_filter(a, win, 1.0, 40.0, BorderMode::Nearest))
+        );
+    }
+}
@@ -1,8 +1,12 @@
 
-use num_traits::{ PrimInt, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Add;
 
+use num_traits::{Bounded, CheckedAdd, NumCast, PrimInt, ToPrimitive, Zero};
 
-pub trait IntConv: Ord + Sized + Clone + Zero {
+
+pub trait IntConv: Ord + Sized + Clone + Zero + NumCast {
     type LargerInt: PrimInt;
     /// Integer value which is at minimum 32bits larger than Self
     /// with unsigned or signed the same as Self
@@ -18,7 +22,7 @@ pub trait IntConv: Ord + Sized + Clone + Zero {
     fn as_larger_int(&self) -> Self::LargerInt;
     fn as_f64(&self) -> f64;
 }
-impl NumConv for u8 {
+impl IntConv for u8 {
     type LargerInt = i64;
     const L_ZERO: Self::LargerInt = 0i64;
     const MAX: Self = u8::MAX;
@@ -48,7 +52,7 @@ impl IntConv for i8 {
     fn as_f64(&self) -> f64 {*self as f64}
 }
 
-impl NumConv for u16 {
+impl IntConv for u16 {
     type LargerInt = i64;
     const L_ZERO: Self::LargerInt = 0i64;
     const MAX: Self = u16::MAX;
@@ -78,7 +82,7 @@ impl IntConv for i16 {
     fn as_f64(&self) -> f64 {*self as f64}
 }
 
-impl NumConv for u32 {
+impl IntConv for u32 {
     type LargerInt = i64;
     const L_ZERO: Self::LargerInt = 0i64;
     const MAX: Self = u32::MAX;
@@ -108,7 +112,7 @@ impl IntConv for i32 {
     fn as_f64(&self) -> f64 {*self as f64}
 }
 
-impl NumConv for u64 {
+impl IntConv for u64 {
     type LargerInt = i128;
     const L_ZERO: Self::LargerInt = 0i128;
     const MAX: Self = u64::MAX;
@@ -138,6 +142,53 @@ impl IntConv for i64 {
     fn as_f64(&self) -> f64 {*self as f64}
 }
 
+impl IntConv for usize {
+    type LargerInt = i128;
+    const L_ZERO: Self::LargerInt = 0i128;
+    const MAX: Self = usize::MAX;
+    const MIN: Self = usize::MIN;
+    #[inline]
+    fn from_f64(f: f64) -> Self {f.round() as usize}
+    #[inline]
+    fn larger_int_as_f64(f:Self::LargerInt)->f64{f as f64}
+    #[inline]
+    fn as_larger_int(&self) -> Self::LargerInt {*self as Self::LargerInt}
+    #[inline]
+    fn as_f64(&self) -> f64 {*self as f64}
+}
+
+impl IntConv for isize {
+    type LargerInt = i128;
+    const L_ZERO: Self::LargerInt = 0i128;
+    const MAX: Self = isize::MAX;
+    const MIN: Self = isize::MIN;
+    #[inline]
+    fn from_f64(f: f64) -> Self {f.round() as isize}
+    #[inline]
+    fn larger_int_as_f64(f:Self::LargerInt)->f64{f as f64}
+    #[inline]
+    fn as_larger_int(&self) -> Self::LargerInt {*self as Self::LargerInt}
+    #[inline]
+    fn as_f64(&self) -> f64 {*self as f64}
+}
+
+/// there is no wider primitive integer, so sums of [i128] windows can overflow
+/// [IntConv::LargerInt], see [WinNum::acc_checked_add]
+impl IntConv for i128 {
+    type LargerInt = i128;
+    const L_ZERO: Self::LargerInt = 0i128;
+    const MAX: Self = i128::MAX;
+    const MIN: Self = i128::MIN;
+    #[inline]
+    fn from_f64(f: f64) -> Self {f.round() as i128}
+    #[inline]
+    fn larger_int_as_f64(f:Self::LargerInt)->f64{f as f64}
+    #[inline]
+    fn as_larger_int(&self) -> Self::LargerInt {*self}
+    #[inline]
+    fn as_f64(&self) -> f64 {*self as f64}
+}
+
 // -------------------------------------------------------------------------------------------------
 // -------------------------------------------------------------------------------------------------
 // -------------------------------------------------------------------------------------------------
@@ -155,7 +206,7 @@ impl SignedInt for u8 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as u8}
+    fn from_f64(f:f64)->Self{f.round() as u8}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
@@ -164,7 +215,7 @@ impl SignedInt for i8 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as i8}
+    fn from_f64(f:f64)->Self{f.round() as i8}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
@@ -173,7 +224,7 @@ impl SignedInt for u16 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as u16}
+    fn from_f64(f:f64)->Self{f.round() as u16}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
@@ -182,7 +233,7 @@ impl SignedInt for i16 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as i16}
+    fn from_f64(f:f64)->Self{f.round() as i16}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
@@ -191,7 +242,7 @@ impl SignedInt for u32 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as u32}
+    fn from_f64(f:f64)->Self{f.round() as u32}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
@@ -200,17 +251,319 @@ impl SignedInt for i32 {
     type LargerS = i64;
     const L_ZERO:Self::LargerS = 0i64;
     fn to_larger(&self)->Self::LargerS{*self as i64}
-    fn from_f64(f:f64)->Self{f as i32}
+    fn from_f64(f:f64)->Self{f.round() as i32}
     fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
     fn usize_to_larger(f:usize)->Self::LargerS{f as i64}
     fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
 }
-pub trait FloatConv{
+impl SignedInt for u64 {
+    type LargerS = i128;
+    const L_ZERO:Self::LargerS = 0i128;
+    fn to_larger(&self)->Self::LargerS{*self as i128}
+    fn from_f64(f:f64)->Self{f.round() as u64}
+    fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
+    fn usize_to_larger(f:usize)->Self::LargerS{f as i128}
+    fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
+}
+impl SignedInt for i64 {
+    type LargerS = i128;
+    const L_ZERO:Self::LargerS = 0i128;
+    fn to_larger(&self)->Self::LargerS{*self as i128}
+    fn from_f64(f:f64)->Self{f.round() as i64}
+    fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
+    fn usize_to_larger(f:usize)->Self::LargerS{f as i128}
+    fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
+}
+impl SignedInt for usize {
+    type LargerS = i128;
+    const L_ZERO:Self::LargerS = 0i128;
+    fn to_larger(&self)->Self::LargerS{*self as i128}
+    fn from_f64(f:f64)->Self{f.round() as usize}
+    fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
+    fn usize_to_larger(f:usize)->Self::LargerS{f as i128}
+    fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
+}
+impl SignedInt for isize {
+    type LargerS = i128;
+    const L_ZERO:Self::LargerS = 0i128;
+    fn to_larger(&self)->Self::LargerS{*self as i128}
+    fn from_f64(f:f64)->Self{f.round() as isize}
+    fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
+    fn usize_to_larger(f:usize)->Self::LargerS{f as i128}
+    fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
+}
+/// squares of [i128] deviations can overflow [SignedInt::LargerS],
+/// [func_fast_std_pure_int](crate::window_functions::func_fast_std_pure_int) falls back to [f64]
+impl SignedInt for i128 {
+    type LargerS = i128;
+    const L_ZERO:Self::LargerS = 0i128;
+    fn to_larger(&self)->Self::LargerS{*self}
+    fn from_f64(f:f64)->Self{f.round() as i128}
+    fn larger_to_f64(f:Self::LargerS)->f64{f as f64}
+    fn usize_to_larger(f:usize)->Self::LargerS{f as i128}
+    fn larger_pow2(f: Self::LargerS) -> Self::LargerS {f.pow(2)}
+}
+/// floating point element types, the float counterpart of [IntConv]
+pub trait FloatConv: PartialOrd + Copy + Zero {
+    /// positive infinity
+    const INFINITY: Self;
+    /// negative infinity
+    const NEG_INFINITY: Self;
+    fn from_f64(f: f64) -> Self;
+    fn as_f64(&self) -> f64;
+    /// IEEE 754 total order, see [f64::total_cmp]
+    fn total_order(&self, other: &Self) -> Ordering;
+}
+impl FloatConv for f32 {
+    const INFINITY: Self = f32::INFINITY;
+    const NEG_INFINITY: Self = f32::NEG_INFINITY;
+    #[inline]
+    fn from_f64(f: f64) -> Self {f as f32}
+    #[inline]
+    fn as_f64(&self) -> f64 {*self as f64}
+    #[inline]
+    fn total_order(&self, other: &Self) -> Ordering {self.total_cmp(other)}
+}
+impl FloatConv for f64 {
+    const INFINITY: Self = f64::INFINITY;
+    const NEG_INFINITY: Self = f64::NEG_INFINITY;
+    #[inline]
+    fn from_f64(f: f64) -> Self {f}
+    #[inline]
+    fn as_f64(&self) -> f64 {*self}
+    #[inline]
+    fn total_order(&self, other: &Self) -> Ordering {self.total_cmp(other)}
+}
+
+// -------------------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------------------
 
+/// element types accepted by the built-in window functions, every [IntConv] type plus
+/// the [FloatConv] types [f32] and [f64]
+///
+/// integer sums use [IntConv::LargerInt] so they are exact, float sums (including [f32]) use [f64]
+pub trait WinNum: PartialOrd + Clone + Zero + NumCast {
+    /// type window elements are summed in
+    type Acc: Copy + Add<Output = Self::Acc>;
+    const ACC_ZERO: Self::Acc;
+    /// `true` for the [IntConv] types
+    const INTEGER: bool;
+    /// Largest possible value, infinity for floats
+    const MAX: Self;
+    /// Smallest possible value, negative infinity for floats
+    const MIN: Self;
+    /// value treated as full scale by the scaled contrast functions,
+    /// [IntConv::MAX] for integers and `1.0` for floats
+    fn full_scale() -> f64;
+    /// `(low, high)` of the values an image of this type covers, used for dynamic ranges,
+    /// `(MIN, MAX)` for integers and `(-1.0, 1.0)` for floats, the same as `skimage`'s `dtype_range`
+    fn value_range() -> (f64, f64);
+    /// integers are rounded and saturated, see [IntConv::from_f64] and [ConversionPolicy::DEFAULT]
+    fn from_f64(f: f64) -> Self;
+    /// `None` when `f` is outside the range of `Self` or NaN for integers, integers are truncated,
+    /// see [ConversionPolicy::convert]
+    fn from_f64_checked(f: f64) -> Option<Self>;
+    fn as_f64(&self) -> f64;
+    /// the exact value of an integer, `None` for floats
+    fn as_i128(&self) -> Option<i128>;
+    fn as_acc(&self) -> Self::Acc;
+    fn acc_as_f64(a: Self::Acc) -> f64;
+    /// `a + x`, `None` when the sum overflows [WinNum::Acc]
+    fn acc_checked_add(a: Self::Acc, x: &Self) -> Option<Self::Acc>;
+    /// `true` when a sum of `volume` elements can overflow [WinNum::Acc],
+    /// only sums which can overflow need to be checked
+    fn acc_may_overflow(volume: usize) -> bool;
+    /// total order used for sorting, [Ord] for integers and [FloatConv::total_order] for floats
+    fn total_order(&self, other: &Self) -> Ordering;
+}
+impl<T: IntConv> WinNum for T {
+    type Acc = T::LargerInt;
+    const ACC_ZERO: Self::Acc = T::L_ZERO;
+    const INTEGER: bool = true;
+    const MAX: Self = <T as IntConv>::MAX;
+    const MIN: Self = <T as IntConv>::MIN;
+    #[inline]
+    fn full_scale() -> f64 {IntConv::as_f64(&<T as IntConv>::MAX)}
+    #[inline]
+    fn value_range() -> (f64, f64) {(IntConv::as_f64(&<T as IntConv>::MIN), IntConv::as_f64(&<T as IntConv>::MAX))}
+    #[inline]
+    fn from_f64(f: f64) -> Self {<T as IntConv>::from_f64(f)}
+    #[inline]
+    fn from_f64_checked(f: f64) -> Option<Self> {<T as NumCast>::from(f)}
+    #[inline]
+    fn as_f64(&self) -> f64 {IntConv::as_f64(self)}
+    #[inline]
+    fn as_i128(&self) -> Option<i128> {self.to_i128()}
+    #[inline]
+    fn as_acc(&self) -> Self::Acc {self.as_larger_int()}
+    #[inline]
+    fn acc_as_f64(a: Self::Acc) -> f64 {T::larger_int_as_f64(a)}
+    #[inline]
+    fn acc_checked_add(a: Self::Acc, x: &Self) -> Option<Self::Acc> {a.checked_add(&x.as_larger_int())}
+    #[inline]
+    fn acc_may_overflow(volume: usize) -> bool {
+        let largest = IntConv::as_f64(&<T as IntConv>::MAX).max(-IntConv::as_f64(&<T as IntConv>::MIN));
+        volume as f64 * largest > <T::LargerInt as Bounded>::max_value().to_f64().unwrap()
+    }
+    #[inline]
+    fn total_order(&self, other: &Self) -> Ordering {self.cmp(other)}
+}
+macro_rules! impl_win_num_float {
+    ($($t:ty),*) => {$(
+        impl WinNum for $t {
+            type Acc = f64;
+            const ACC_ZERO: Self::Acc = 0f64;
+            const INTEGER: bool = false;
+            const MAX: Self = <$t as FloatConv>::INFINITY;
+            const MIN: Self = <$t as FloatConv>::NEG_INFINITY;
+            #[inline]
+            fn full_scale() -> f64 {1f64}
+            #[inline]
+            fn value_range() -> (f64, f64) {(-1f64, 1f64)}
+            #[inline]
+            fn from_f64(f: f64) -> Self {<$t as FloatConv>::from_f64(f)}
+            #[inline]
+            fn from_f64_checked(f: f64) -> Option<Self> {
+                let x = <$t as FloatConv>::from_f64(f);
+                match f.is_finite() && x.is_infinite() {
+                    true => None,
+                    false => Some(x),
+                }
+            }
+            #[inline]
+            fn as_f64(&self) -> f64 {FloatConv::as_f64(self)}
+            #[inline]
+            fn as_i128(&self) -> Option<i128> {None}
+            #[inline]
+            fn as_acc(&self) -> Self::Acc {FloatConv::as_f64(self)}
+            #[inline]
+            fn acc_as_f64(a: Self::Acc) -> f64 {a}
+            #[inline]
+            fn acc_checked_add(a: Self::Acc, x: &Self) -> Option<Self::Acc> {Some(a + FloatConv::as_f64(x))}
+            #[inline]
+            fn acc_may_overflow(_volume: usize) -> bool {false}
+            #[inline]
+            fn total_order(&self, other: &Self) -> Ordering {FloatConv::total_order(self, other)}
+        }
+    )*};
+}
+impl_win_num_float!(f32, f64);
+
+/// element types a filter with a signed or fractional result can be written into,
+/// integer types are rounded and saturated with [IntConv::from_f64]
+pub trait FilterOutput: Copy + Send + 'static {
+    fn from_filter_f64(f: f64) -> Self;
+}
+impl<T: IntConv + Copy + Send + 'static> FilterOutput for T {
+    #[inline]
+    fn from_filter_f64(f: f64) -> Self {T::from_f64(f)}
+}
+impl FilterOutput for f32 {
+    #[inline]
+    fn from_filter_f64(f: f64) -> Self {f as f32}
+}
+impl FilterOutput for f64 {
+    #[inline]
+    fn from_filter_f64(f: f64) -> Self {f}
+}
+
+// -------------------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------------------
+// -------------------------------------------------------------------------------------------------
+
+/// how a real valued window result is rounded to an integer output, floats are not rounded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// to the nearest integer, halfway cases to the even integer
+    HalfEven,
+    /// to the nearest integer, halfway cases away from zero (the rounding of [f64::round])
+    HalfAway,
+    /// towards negative infinity
+    Floor,
+    /// towards positive infinity
+    Ceil,
+    /// towards zero, the rounding of python's `int()`
+    Truncate,
+}
+impl Rounding {
+    #[inline]
+    pub fn apply(self, f: f64) -> f64 {
+        match self {
+            Rounding::HalfEven => f.round_ties_even(),
+            Rounding::HalfAway => f.round(),
+            Rounding::Floor => f.floor(),
+            Rounding::Ceil => f.ceil(),
+            Rounding::Truncate => f.trunc(),
+        }
+    }
+}
+
+/// what happens to a rounded result which does not fit the output type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overflow {
+    /// clamp to the range of the output type, NaN becomes `0` for integers
+    Saturate,
+    /// return a [ConversionError], NaN is an error for integers
+    Error,
+}
+
+/// rounding and overflow handling used to write a real valued window result into the element type
+///
+/// ```
+/// use ndarray_threaded_window::integer_conversion_traits::ConversionPolicy;
+/// assert_eq!(ConversionPolicy::DEFAULT.convert::<u8>(2.5), Ok(3));
+/// assert_eq!(ConversionPolicy::NUMPY.convert::<u8>(2.5), Ok(2));
+/// assert!(ConversionPolicy::NUMPY.convert::<u8>(256.0).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConversionPolicy {
+    pub rounding: Rounding,
+    pub overflow: Overflow,
+}
+impl ConversionPolicy {
+    /// the conversion of [WinNum::from_f64], round half away from zero and saturate
+    pub const DEFAULT: Self = Self { rounding: Rounding::HalfAway, overflow: Overflow::Saturate };
+    /// matches `int(np.std(...))` for the numpy compatible built-ins, truncate towards zero,
+    /// values numpy could not store in the input dtype are errors
+    pub const NUMPY: Self = Self { rounding: Rounding::Truncate, overflow: Overflow::Error };
+
+    /// `f` rounded (integers only) and converted to `T`
+    #[inline]
+    pub fn convert<T: WinNum>(&self, f: f64) -> Result<T, ConversionError> {
+        let r = match T::INTEGER {
+            true => self.rounding.apply(f),
+            false => f,
+        };
+        match self.overflow {
+            Overflow::Saturate => Ok(T::from_f64(r)),
+            Overflow::Error => T::from_f64_checked(r).ok_or(ConversionError(f)),
+        }
+    }
+}
+impl Default for ConversionPolicy {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
-impl FloatConv for f32{
 
+/// window result which did not fit the output type with [Overflow::Error]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConversionError(pub f64);
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "window result {} does not fit in the output type", self.0)
+    }
 }
-impl FloatConv for f64{
+impl std::error::Error for ConversionError {}
 
+/// a window sum overflowed [WinNum::Acc] in a checked accumulation,
+/// see [check_window_sums](crate::array_threading::check_window_sums)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccumulationOverflow;
+impl fmt::Display for AccumulationOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "window sum overflows the integer accumulator")
+    }
 }
+impl std::error::Error for AccumulationOverflow {}
@@ -1,11 +1,26 @@
-use integer_conversion_traits::IntConv;
+#[cfg(feature = "default")]
+use integer_conversion_traits::WinNum;
 use window_functions::WinFunc;
 
 use crate::array_shape_traits::{get_proc_count, set_thread_env_var, WinSh};
 
 pub mod array_shape_traits;
 pub mod array_threading;
+pub mod binary;
+pub mod border_handling;
+pub mod categorical;
+pub mod contrast;
+pub mod extrema;
+pub mod filters;
 pub mod integer_conversion_traits;
+pub mod morphology;
+pub mod paired;
+pub mod peaks;
+pub mod similarity;
+pub mod statistics;
+pub mod template;
+pub mod texture;
+pub mod threshold;
 pub mod window_functions;
 
 #[cfg(feature = "default")]
@@ -15,12 +30,323 @@ mod python_lib {
         PyArray, PyReadonlyArray1, PyReadonlyArrayDyn, ToPyArray,
     };
     use pyo3::{
-        prelude::pyfunction, prelude::pymodule, prelude::PyModule, prelude::PyResult,
-        prelude::Python, wrap_pyfunction, wrap_pymodule, IntoPy, PyObject,
+        exceptions::{PyOverflowError, PyValueError}, prelude::pyfunction, prelude::pymodule, prelude::PyModule,
+        prelude::PyResult, prelude::Python, wrap_pyfunction, wrap_pymodule, IntoPy, PyObject,
     };
 
+    use crate::border_handling::BorderMode;
+
     use super::*;
 
+    /// Dynamic to Static array switching for functions generic over the dimension,
+    /// `$body` is run with `$arr` bound to the static array and the result is returned as [IxDyn],
+    /// with `scalar` the result of `$body` is returned unchanged
+    macro_rules! with_static_dim {
+        ($a:expr, |$arr:ident| $body:expr) => {
+            with_static_dim!(scalar $a, |$arr| $body.into_dyn())
+        };
+        (scalar $a:expr, |$arr:ident| $body:expr) => {{
+            let a = $a;
+            let shape = a.shape().to_vec();
+            match shape.len() {
+                1 => {
+                    let $arr = a.into_shape(Ix1::from_slice(&shape)).unwrap();
+                    $body
+                }
+                2 => {
+                    let $arr = a.into_shape(Ix2::from_slice(&shape)).unwrap();
+                    $body
+                }
+                3 => {
+                    let $arr = a.into_shape(Ix3::from_slice(&shape)).unwrap();
+                    $body
+                }
+                4 => {
+                    let $arr = a.into_shape(Ix4::from_slice(&shape)).unwrap();
+                    $body
+                }
+                5 => {
+                    let $arr = a.into_shape(Ix5::from_slice(&shape)).unwrap();
+                    $body
+                }
+                _ => panic!("Array Shape Not Implemented"),
+            }
+        }};
+    }
+
+    /// border mode names used by the python functions: "valid", "constant", "nearest", "reflect"
+    fn border_from_str<T: WinNum>(mode: &str, cval: f64) -> PyResult<BorderMode<T>> {
+        match mode {
+            "valid" => Ok(BorderMode::Valid),
+            "constant" => Ok(BorderMode::Constant(T::from_f64(cval))),
+            "nearest" => Ok(BorderMode::Nearest),
+            "reflect" => Ok(BorderMode::Reflect),
+            x => Err(PyValueError::new_err(format!("unknown border mode {:?}", x))),
+        }
+    }
+
+    /// [border_from_str] for bool arrays, `cval` is the value used with "constant"
+    fn border_from_bool_str(mode: &str, cval: bool) -> PyResult<BorderMode<bool>> {
+        Ok(border_from_str::<u8>(mode, 0f64)?.map_constant(|_| cval))
+    }
+
+    /// local binary pattern method names used by the python functions,
+    /// the same names as `skimage.feature.local_binary_pattern`
+    fn lbp_method_from_str(method: &str) -> PyResult<texture::LbpMethod> {
+        match method {
+            "default" => Ok(texture::LbpMethod::Default),
+            "ror" => Ok(texture::LbpMethod::RotationInvariant),
+            "uniform" => Ok(texture::LbpMethod::Uniform),
+            "nri_uniform" => Ok(texture::LbpMethod::NonRotationInvariantUniform),
+            x => Err(PyValueError::new_err(format!("unknown local binary pattern method {:?}", x))),
+        }
+    }
+
+    /// local threshold method names used by the python functions:
+    /// "niblack", "sauvola", "wolf", "bernsen", parameters left as `None` use their usual values
+    fn threshold_method_from_str<T: WinNum>(
+        method: &str,
+        k: Option<f64>,
+        r: Option<f64>,
+        contrast_threshold: f64,
+    ) -> PyResult<threshold::ThresholdMethod> {
+        match method {
+            "niblack" => Ok(threshold::ThresholdMethod::Niblack { k: k.unwrap_or(-0.2) }),
+            "sauvola" => Ok(threshold::ThresholdMethod::Sauvola {
+                k: k.unwrap_or(0.2),
+                r: r.unwrap_or_else(threshold::sauvola_default_r::<T>),
+            }),
+            "wolf" => Ok(threshold::ThresholdMethod::Wolf { k: k.unwrap_or(0.5) }),
+            "bernsen" => Ok(threshold::ThresholdMethod::Bernsen { contrast_threshold }),
+            x => Err(PyValueError::new_err(format!("unknown threshold method {:?}", x))),
+        }
+    }
+
+    /// gradient operator names used by the python functions: "sobel", "prewitt", "scharr"
+    fn gradient_operator_from_str(operator: &str) -> PyResult<filters::GradientOperator> {
+        match operator {
+            "sobel" => Ok(filters::GradientOperator::Sobel),
+            "prewitt" => Ok(filters::GradientOperator::Prewitt),
+            "scharr" => Ok(filters::GradientOperator::Scharr),
+            x => Err(PyValueError::new_err(format!("unknown gradient operator {:?}", x))),
+        }
+    }
+
+    /// contrast measure names used by the python functions: "rms", "michelson", "weber"
+    fn contrast_measure_from_str(measure: &str) -> PyResult<contrast::ContrastMeasure> {
+        match measure {
+            "rms" => Ok(contrast::ContrastMeasure::Rms),
+            "michelson" => Ok(contrast::ContrastMeasure::Michelson),
+            "weber" => Ok(contrast::ContrastMeasure::Weber),
+            x => Err(PyValueError::new_err(format!("unknown contrast measure {:?}", x))),
+        }
+    }
+
+    /// plateau names used by the python functions: "all", "first", "centre"
+    fn plateau_from_str(plateau: &str) -> PyResult<peaks::Plateau> {
+        match plateau {
+            "all" => Ok(peaks::Plateau::All),
+            "first" => Ok(peaks::Plateau::First),
+            "centre" => Ok(peaks::Plateau::Centre),
+            x => Err(PyValueError::new_err(format!("unknown plateau handling {:?}", x))),
+        }
+    }
+
+    /// extremum names used by the python functions: "max", "min"
+    fn extremum_from_str(extremum: &str) -> PyResult<extrema::Extremum> {
+        match extremum {
+            "max" => Ok(extrema::Extremum::Max),
+            "min" => Ok(extrema::Extremum::Min),
+            x => Err(PyValueError::new_err(format!("unknown extremum {:?}", x))),
+        }
+    }
+
+    /// moment names used by the python functions: "central" (with `order`), "skewness", "kurtosis"
+    fn moment_from_str(moment: &str, order: u32) -> PyResult<statistics::Moment> {
+        match moment {
+            "central" => Ok(statistics::Moment::Central(order)),
+            "skewness" => Ok(statistics::Moment::Skewness),
+            "kurtosis" => Ok(statistics::Moment::ExcessKurtosis),
+            x => Err(PyValueError::new_err(format!("unknown moment {:?}", x))),
+        }
+    }
+
+    /// robust statistic names used by the python functions: "mad", "trimmed_mean", "winsorised_mean"
+    fn robust_statistic_from_str(statistic: &str, alpha: f64, scaled: bool) -> PyResult<statistics::RobustStatistic> {
+        if !(0f64..0.5).contains(&alpha) {
+            return Err(PyValueError::new_err("alpha must be at least 0 and less than 0.5"));
+        }
+        match statistic {
+            "mad" => Ok(statistics::RobustStatistic::Mad { scaled }),
+            "trimmed_mean" => Ok(statistics::RobustStatistic::TrimmedMean { alpha }),
+            "winsorised_mean" => Ok(statistics::RobustStatistic::WinsorisedMean { alpha }),
+            x => Err(PyValueError::new_err(format!("unknown robust statistic {:?}", x))),
+        }
+    }
+
+    /// NaN aware reduction names used by the python functions:
+    /// "max", "min", "mean", "std" (with `ddof`), "median"
+    fn nan_reduction_from_str(reduction: &str, ddof: usize) -> PyResult<window_functions::NanReduction> {
+        match reduction {
+            "max" => Ok(window_functions::NanReduction::Max),
+            "min" => Ok(window_functions::NanReduction::Min),
+            "mean" => Ok(window_functions::NanReduction::Mean),
+            "std" => Ok(window_functions::NanReduction::Std { ddof }),
+            "median" => Ok(window_functions::NanReduction::Median),
+            x => Err(PyValueError::new_err(format!("unknown nan reduction {:?}", x))),
+        }
+    }
+
+    /// NaN policy names used by the python functions: "propagate", "omit" (with `min_count`)
+    fn nan_policy_from_str(policy: &str, min_count: usize) -> PyResult<window_functions::NanPolicy> {
+        match policy {
+            "propagate" => Ok(window_functions::NanPolicy::Propagate),
+            "omit" => Ok(window_functions::NanPolicy::Omit { min_count }),
+            x => Err(PyValueError::new_err(format!("unknown nan policy {:?}", x))),
+        }
+    }
+
+    /// rounding and overflow names used by the python functions,
+    /// "half_even", "half_away", "floor", "ceil", "truncate" and "saturate", "error"
+    fn conversion_policy_from_str(
+        rounding: &str,
+        overflow: &str,
+    ) -> PyResult<integer_conversion_traits::ConversionPolicy> {
+        use integer_conversion_traits::{Overflow, Rounding};
+        let rounding = match rounding {
+            "half_even" => Rounding::HalfEven,
+            "half_away" => Rounding::HalfAway,
+            "floor" => Rounding::Floor,
+            "ceil" => Rounding::Ceil,
+            "truncate" => Rounding::Truncate,
+            x => return Err(PyValueError::new_err(format!("unknown rounding {:?}", x))),
+        };
+        let overflow = match overflow {
+            "saturate" => Overflow::Saturate,
+            "error" => Overflow::Error,
+            x => return Err(PyValueError::new_err(format!("unknown overflow handling {:?}", x))),
+        };
+        Ok(integer_conversion_traits::ConversionPolicy { rounding, overflow })
+    }
+
+    /// a float64 filter result written into the dtype named by `output` with
+    /// [FilterOutput::from_filter_f64](integer_conversion_traits::FilterOutput::from_filter_f64),
+    /// integer outputs are rounded and saturated
+    fn filter_output(py: Python, out: Array<f64, IxDyn>, output: &str) -> PyResult<PyObject> {
+        fn typed<O>(py: Python, out: Array<f64, IxDyn>) -> PyObject
+        where
+            O: integer_conversion_traits::FilterOutput + numpy::Element,
+        {
+            out.mapv(O::from_filter_f64).to_pyarray(py).into_py(py)
+        }
+        Ok(match output {
+            "float64" => out.to_pyarray(py).into_py(py),
+            "float32" => typed::<f32>(py, out),
+            "int8" => typed::<i8>(py, out),
+            "int16" => typed::<i16>(py, out),
+            "int32" => typed::<i32>(py, out),
+            "int64" => typed::<i64>(py, out),
+            "uint8" => typed::<u8>(py, out),
+            "uint16" => typed::<u16>(py, out),
+            "uint32" => typed::<u32>(py, out),
+            "uint64" => typed::<u64>(py, out),
+            x => return Err(PyValueError::new_err(format!("unsupported output dtype {:?}", x))),
+        })
+    }
+
+    /// converted masked window results, windows without enough valid elements (`None`)
+    /// are set to `fill`
+    fn convert_masked<T, D>(
+        values: Array<Option<Option<Result<T, integer_conversion_traits::ConversionError>>>, D>,
+        fill: T,
+    ) -> PyResult<Array<T, D>>
+    where
+        T: WinNum + Copy,
+        D: Dimension,
+    {
+        convert_checked(values.mapv(|x| x.unwrap_or(Some(Ok(fill)))))
+    }
+
+    /// converted window results, `None` where the window sum overflowed (see
+    /// [array_threading::window_sum_overflows]), both errors are raised as OverflowError
+    fn convert_checked<T, D>(
+        values: Array<Option<Result<T, integer_conversion_traits::ConversionError>>, D>,
+    ) -> PyResult<Array<T, D>>
+    where
+        T: WinNum + Copy,
+        D: Dimension,
+    {
+        let out = values
+            .iter()
+            .map(|x| match x {
+                Some(x) => x.map_err(|e| PyOverflowError::new_err(e.to_string())),
+                None => Err(PyOverflowError::new_err(integer_conversion_traits::AccumulationOverflow.to_string())),
+            })
+            .collect::<PyResult<Vec<T>>>()?;
+        Ok(Array::from_shape_vec(values.raw_dim(), out).unwrap())
+    }
+
+    /// tie breaking names used by the python functions: "smallest", "largest", "centre"
+    fn tie_break_from_str(tie: &str) -> PyResult<categorical::TieBreak> {
+        match tie {
+            "smallest" => Ok(categorical::TieBreak::Smallest),
+            "largest" => Ok(categorical::TieBreak::Largest),
+            "centre" => Ok(categorical::TieBreak::KeepCentre),
+            x => Err(PyValueError::new_err(format!("unknown tie breaking {:?}", x))),
+        }
+    }
+
+    /// glcm feature names used by the python functions:
+    /// "contrast", "homogeneity", "energy", "correlation", "entropy"
+    fn glcm_features_from_str(features: &[String]) -> PyResult<Vec<texture::GlcmFeature>> {
+        features
+            .iter()
+            .map(|f| match f.as_str() {
+                "contrast" => Ok(texture::GlcmFeature::Contrast),
+                "homogeneity" => Ok(texture::GlcmFeature::Homogeneity),
+                "energy" => Ok(texture::GlcmFeature::Energy),
+                "correlation" => Ok(texture::GlcmFeature::Correlation),
+                "entropy" => Ok(texture::GlcmFeature::Entropy),
+                x => Err(PyValueError::new_err(format!("unknown glcm feature {:?}", x))),
+            })
+            .collect()
+    }
+
+    /// two array statistic names used by the python functions: "correlation", "covariance", "weighted_mean"
+    fn pair_statistic_from_str(statistic: &str) -> PyResult<paired::PairStatistic> {
+        match statistic {
+            "correlation" => Ok(paired::PairStatistic::Correlation),
+            "covariance" => Ok(paired::PairStatistic::Covariance),
+            "weighted_mean" => Ok(paired::PairStatistic::WeightedMean),
+            x => Err(PyValueError::new_err(format!("unknown two array statistic {:?}", x))),
+        }
+    }
+
+    /// template score names used by the python functions: "ncc", "ssd", "sad"
+    fn template_score_from_str(score: &str) -> PyResult<template::TemplateScore> {
+        match score {
+            "ncc" => Ok(template::TemplateScore::Ncc),
+            "ssd" => Ok(template::TemplateScore::Ssd),
+            "sad" => Ok(template::TemplateScore::Sad),
+            x => Err(PyValueError::new_err(format!("unknown template score {:?}", x))),
+        }
+    }
+
+    /// ssim settings used by the python functions, `window` is "gaussian" (using `sigma`)
+    /// or "uniform" (using `size`)
+    fn ssim_params_from(window: &str, sigma: f64, size: usize, k1: f64, k2: f64) -> PyResult<similarity::SsimParams> {
+        let window = match window {
+            "gaussian" if sigma > 0f64 => similarity::SsimWindow::Gaussian {
+                sigma,
+                truncate: 3.5,
+            },
+            "uniform" if size > 0 => similarity::SsimWindow::Uniform(size),
+            "gaussian" | "uniform" => return Err(PyValueError::new_err("sigma and size must be positive")),
+            x => return Err(PyValueError::new_err(format!("unknown ssim window {:?}", x))),
+        };
+        Ok(similarity::SsimParams { window, k1, k2 })
+    }
+
     /// ndarray_threaded_window
     #[pymodule]
     fn ndarray_threaded_window(_py: Python, m: &PyModule) -> PyResult<()> {
@@ -85,7 +411,7 @@ mod python_lib {
         window: &[usize],
     ) -> Array<T, IxDyn>
     where
-        T: IntConv + Clone + Copy + Send + 'static,
+        T: WinNum + Copy + Send + 'static,
     {
         let binding = a.dim().clone();
         let sl = binding.slice();
@@ -196,6 +522,1139 @@ mod python_lib {
         ) -> &'py PyArray<i32, IxDyn> {
             sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py)
         }
+        m.add_function(wrap_pyfunction!(apply_window_for_dyn_u64, m)?)?;
+        #[pyfunction]
+        fn apply_window_for_dyn_u64<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<u64>,
+            m: usize,
+            window: Vec<usize>,
+        ) -> &'py PyArray<u64, IxDyn> {
+            sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py)
+        }
+        m.add_function(wrap_pyfunction!(apply_window_for_dyn_i64, m)?)?;
+        #[pyfunction]
+        fn apply_window_for_dyn_i64<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<i64>,
+            m: usize,
+            window: Vec<usize>,
+        ) -> &'py PyArray<i64, IxDyn> {
+            sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py)
+        }
+        m.add_function(wrap_pyfunction!(apply_window_for_dyn_f32, m)?)?;
+        #[pyfunction]
+        fn apply_window_for_dyn_f32<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<f32>,
+            m: usize,
+            window: Vec<usize>,
+        ) -> &'py PyArray<f32, IxDyn> {
+            sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py)
+        }
+        m.add_function(wrap_pyfunction!(apply_window_for_dyn_f64, m)?)?;
+        #[pyfunction]
+        fn apply_window_for_dyn_f64<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<f64>,
+            m: usize,
+            window: Vec<usize>,
+        ) -> &'py PyArray<f64, IxDyn> {
+            sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py)
+        }
+
+        macro_rules! add_apply_window_masked {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    m: usize,
+                    window: Vec<usize>,
+                    valid_mask: Option<PyReadonlyArrayDyn<bool>>,
+                    nodata: Option<$t>,
+                    fill: $t,
+                    min_valid: usize,
+                    rounding: &str,
+                    overflow: &str,
+                    checked: bool,
+                ) -> PyResult<(&'py PyArray<$t, IxDyn>, &'py PyArray<usize, IxDyn>)> {
+                    let policy = conversion_policy_from_str(rounding, overflow)?;
+                    let a = a.to_owned_array();
+                    let mut mask = Array::from_elem(a.raw_dim(), true);
+                    if let Some(valid_mask) = valid_mask {
+                        if valid_mask.shape() != a.shape() {
+                            return Err(PyValueError::new_err("valid_mask must have the same shape as the array"));
+                        }
+                        mask = valid_mask.to_owned_array();
+                    }
+                    if let Some(nodata) = nodata {
+                        mask = mask & array_threading::nodata_mask(&a, &nodata);
+                    }
+                    window_functions::get_func_name(m).map_err(PyValueError::new_err)?;
+                    let checked = checked && window_functions::get_func_accumulates(m).unwrap();
+                    // every function which sums the window has a converted version
+                    let converted = (checked || policy != integer_conversion_traits::ConversionPolicy::DEFAULT)
+                        && window_functions::get_func_converted::<$t, Ix1>(m).unwrap().is_some();
+                    // assigned in every dimension branch of the macro
+                    let counts;
+                    let out = with_static_dim!(a, |arr| {
+                        let mask = mask.into_shape(arr.raw_dim()).unwrap();
+                        let win = WinSh::from_slice(window.as_slice());
+                        match converted {
+                            true => {
+                                let func = window_functions::get_func_converted(m).unwrap().unwrap();
+                                let (out, c) = array_threading::thread_over_any_window_masked(
+                                    arr, &mask, win, move |w| match checked && array_threading::window_sum_overflows(&w) {
+                                        true => Some(None),
+                                        false => Some(Some(func(w, policy))),
+                                    }, None, min_valid,
+                                );
+                                counts = c.into_dyn();
+                                convert_masked(out, fill)?
+                            }
+                            false => {
+                                let (out, c) = array_threading::thread_over_any_window_masked(
+                                    arr, &mask, win, window_functions::get_func(m).unwrap(), fill, min_valid,
+                                );
+                                counts = c.into_dyn();
+                                out
+                            }
+                        }
+                    });
+                    Ok((out.to_pyarray(py), counts.to_pyarray(py)))
+                }
+            )*};
+        }
+        add_apply_window_masked!(
+            apply_window_masked_for_dyn_u8: u8,
+            apply_window_masked_for_dyn_u16: u16,
+            apply_window_masked_for_dyn_u32: u32,
+            apply_window_masked_for_dyn_i8: i8,
+            apply_window_masked_for_dyn_i16: i16,
+            apply_window_masked_for_dyn_i32: i32,
+            apply_window_masked_for_dyn_u64: u64,
+            apply_window_masked_for_dyn_i64: i64,
+            apply_window_masked_for_dyn_f32: f32,
+            apply_window_masked_for_dyn_f64: f64
+        );
+
+        macro_rules! add_apply_window_converted {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    m: usize,
+                    window: Vec<usize>,
+                    rounding: &str,
+                    overflow: &str,
+                    checked: bool,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let policy = conversion_policy_from_str(rounding, overflow)?;
+                    let converted = window_functions::get_func_converted::<$t, Ix1>(m).map_err(PyValueError::new_err)?;
+                    if converted.is_none() {
+                        return Ok(sub_apply_window_dyn(a.to_owned_array(), m, window.as_slice()).to_pyarray(py));
+                    }
+                    let checked = checked && window_functions::get_func_accumulates(m).unwrap();
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        let func = window_functions::get_func_converted(m).unwrap().unwrap();
+                        match checked {
+                            true => {
+                                let values = array_threading::thread_over_any_window_map(arr, win, move |w| {
+                                    match array_threading::window_sum_overflows(&w) {
+                                        true => None,
+                                        false => Some(func(w, policy)),
+                                    }
+                                });
+                                convert_checked(values)?
+                            }
+                            false => array_threading::thread_over_any_window_converted(arr, win, func, policy)
+                                .map_err(|e| PyOverflowError::new_err(e.to_string()))?,
+                        }
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_apply_window_converted!(
+            apply_window_converted_for_dyn_u8: u8,
+            apply_window_converted_for_dyn_u16: u16,
+            apply_window_converted_for_dyn_u32: u32,
+            apply_window_converted_for_dyn_i8: i8,
+            apply_window_converted_for_dyn_i16: i16,
+            apply_window_converted_for_dyn_i32: i32,
+            apply_window_converted_for_dyn_u64: u64,
+            apply_window_converted_for_dyn_i64: i64,
+            apply_window_converted_for_dyn_f32: f32,
+            apply_window_converted_for_dyn_f64: f64
+        );
+
+        m.add_function(wrap_pyfunction!(binary_morphology, m)?)?;
+        #[pyfunction]
+        fn binary_morphology<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<bool>,
+            footprint: PyReadonlyArrayDyn<bool>,
+            operation: &str,
+            mode: &str,
+            cval: bool,
+        ) -> PyResult<&'py PyArray<bool, IxDyn>> {
+            let border = border_from_bool_str(mode, cval)?;
+            if !["erosion", "dilation", "opening", "closing"].contains(&operation) {
+                return Err(PyValueError::new_err(format!("unknown binary operation {:?}", operation)));
+            }
+            if footprint.ndim() != a.ndim() {
+                return Err(PyValueError::new_err("footprint must have the same number of dimensions as the array"));
+            }
+            let footprint = footprint.to_owned_array();
+            let out = with_static_dim!(a.to_owned_array(), |arr| {
+                let fp = footprint.into_dimensionality().unwrap();
+                match operation {
+                    "erosion" => binary::binary_erosion(arr, &fp, border),
+                    "dilation" => binary::binary_dilation(arr, &fp, border),
+                    "opening" => binary::binary_opening(arr, &fp, border),
+                    _ => binary::binary_closing(arr, &fp, border),
+                }
+            });
+            Ok(out.to_pyarray(py))
+        }
+
+        m.add_function(wrap_pyfunction!(neighbour_count, m)?)?;
+        #[pyfunction]
+        fn neighbour_count<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<bool>,
+            footprint: PyReadonlyArrayDyn<bool>,
+            mode: &str,
+            cval: bool,
+        ) -> PyResult<&'py PyArray<usize, IxDyn>> {
+            let border = border_from_bool_str(mode, cval)?;
+            if footprint.ndim() != a.ndim() {
+                return Err(PyValueError::new_err("footprint must have the same number of dimensions as the array"));
+            }
+            let footprint = footprint.to_owned_array();
+            let out = with_static_dim!(a.to_owned_array(), |arr| {
+                let fp = footprint.into_dimensionality().unwrap();
+                binary::neighbour_count(arr, &fp, border)
+            });
+            Ok(out.to_pyarray(py))
+        }
+
+        macro_rules! add_grey_morphology {
+            ($($name:ident, $hit_or_miss:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    footprint: PyReadonlyArrayDyn<bool>,
+                    operation: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let operations = ["erosion", "dilation", "opening", "closing", "gradient", "white_tophat", "black_tophat"];
+                    if !operations.contains(&operation) {
+                        return Err(PyValueError::new_err(format!("unknown grey operation {:?}", operation)));
+                    }
+                    if footprint.ndim() != a.ndim() {
+                        return Err(PyValueError::new_err("footprint must have the same number of dimensions as the array"));
+                    }
+                    let footprint = footprint.to_owned_array();
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let fp = footprint.into_dimensionality().unwrap();
+                        match operation {
+                            "erosion" => morphology::grey_erosion(arr, &fp, border),
+                            "dilation" => morphology::grey_dilation(arr, &fp, border),
+                            "opening" => morphology::grey_opening(arr, &fp, border),
+                            "closing" => morphology::grey_closing(arr, &fp, border),
+                            "gradient" => morphology::morphological_gradient(arr, &fp, border),
+                            "white_tophat" => morphology::white_tophat(arr, &fp, border),
+                            _ => morphology::black_tophat(arr, &fp, border),
+                        }
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+
+                m.add_function(wrap_pyfunction!($hit_or_miss, m)?)?;
+                #[pyfunction]
+                fn $hit_or_miss<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    hit: PyReadonlyArrayDyn<bool>,
+                    miss: PyReadonlyArrayDyn<bool>,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if hit.ndim() != a.ndim() || hit.shape() != miss.shape() {
+                        return Err(PyValueError::new_err(
+                            "hit and miss must have the same shape and number of dimensions as the array",
+                        ));
+                    }
+                    let (hit, miss) = (hit.to_owned_array(), miss.to_owned_array());
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let (hit, miss) = (hit.into_dimensionality().unwrap(), miss.into_dimensionality().unwrap());
+                        morphology::hit_or_miss(arr, &hit, &miss, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_grey_morphology!(
+            grey_morphology_for_dyn_u8, hit_or_miss_for_dyn_u8: u8,
+            grey_morphology_for_dyn_u16, hit_or_miss_for_dyn_u16: u16,
+            grey_morphology_for_dyn_u32, hit_or_miss_for_dyn_u32: u32,
+            grey_morphology_for_dyn_i8, hit_or_miss_for_dyn_i8: i8,
+            grey_morphology_for_dyn_i16, hit_or_miss_for_dyn_i16: i16,
+            grey_morphology_for_dyn_i32, hit_or_miss_for_dyn_i32: i32,
+            grey_morphology_for_dyn_u64, hit_or_miss_for_dyn_u64: u64,
+            grey_morphology_for_dyn_i64, hit_or_miss_for_dyn_i64: i64,
+            grey_morphology_for_dyn_f32, hit_or_miss_for_dyn_f32: f32,
+            grey_morphology_for_dyn_f64, hit_or_miss_for_dyn_f64: f64
+        );
+
+        macro_rules! add_gaussian_filter {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    sigma: Vec<f64>,
+                    truncate: f64,
+                    mode: &str,
+                    cval: f64,
+                    rounding: &str,
+                    overflow: &str,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let policy = conversion_policy_from_str(rounding, overflow)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        filters::gaussian_filter_converted(arr, &sigma, truncate, border, policy)
+                            .map_err(|e| PyOverflowError::new_err(e.to_string()))?
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_gaussian_filter!(
+            gaussian_filter_for_dyn_u8: u8,
+            gaussian_filter_for_dyn_u16: u16,
+            gaussian_filter_for_dyn_u32: u32,
+            gaussian_filter_for_dyn_i8: i8,
+            gaussian_filter_for_dyn_i16: i16,
+            gaussian_filter_for_dyn_i32: i32,
+            gaussian_filter_for_dyn_u64: u64,
+            gaussian_filter_for_dyn_i64: i64,
+            gaussian_filter_for_dyn_f32: f32,
+            gaussian_filter_for_dyn_f64: f64
+        );
+
+        macro_rules! add_gradient_filters {
+            ($($axis_name:ident, $magnitude_name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($axis_name, m)?)?;
+                #[pyfunction]
+                fn $axis_name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    axis: usize,
+                    operator: &str,
+                    mode: &str,
+                    cval: f64,
+                    output: &str,
+                ) -> PyResult<PyObject> {
+                    let operator = gradient_operator_from_str(operator)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if axis >= a.ndim() {
+                        return Err(PyValueError::new_err(format!("axis {} out of range", axis)));
+                    }
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        filters::gradient_axis::<$t, f64, _>(arr, axis, operator, border)
+                    });
+                    filter_output(py, out, output)
+                }
+
+                m.add_function(wrap_pyfunction!($magnitude_name, m)?)?;
+                #[pyfunction]
+                fn $magnitude_name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    operator: &str,
+                    mode: &str,
+                    cval: f64,
+                    output: &str,
+                ) -> PyResult<PyObject> {
+                    let operator = gradient_operator_from_str(operator)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        filters::gradient_magnitude::<$t, f64, _>(arr, operator, border)
+                    });
+                    filter_output(py, out, output)
+                }
+            )*};
+        }
+        add_gradient_filters!(
+            gradient_axis_for_dyn_u8, gradient_magnitude_for_dyn_u8: u8,
+            gradient_axis_for_dyn_u16, gradient_magnitude_for_dyn_u16: u16,
+            gradient_axis_for_dyn_u32, gradient_magnitude_for_dyn_u32: u32,
+            gradient_axis_for_dyn_i8, gradient_magnitude_for_dyn_i8: i8,
+            gradient_axis_for_dyn_i16, gradient_magnitude_for_dyn_i16: i16,
+            gradient_axis_for_dyn_i32, gradient_magnitude_for_dyn_i32: i32,
+            gradient_axis_for_dyn_u64, gradient_magnitude_for_dyn_u64: u64,
+            gradient_axis_for_dyn_i64, gradient_magnitude_for_dyn_i64: i64,
+            gradient_axis_for_dyn_f32, gradient_magnitude_for_dyn_f32: f32,
+            gradient_axis_for_dyn_f64, gradient_magnitude_for_dyn_f64: f64
+        );
+
+        macro_rules! add_laplace_filters {
+            ($($laplace:ident, $log:ident, $score:ident, $map:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($laplace, m)?)?;
+                #[pyfunction]
+                fn $laplace<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    mode: &str,
+                    cval: f64,
+                    output: &str,
+                ) -> PyResult<PyObject> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        filters::laplace::<$t, f64, _>(arr, border)
+                    });
+                    filter_output(py, out, output)
+                }
+
+                m.add_function(wrap_pyfunction!($log, m)?)?;
+                #[pyfunction]
+                fn $log<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    sigma: Vec<f64>,
+                    truncate: f64,
+                    mode: &str,
+                    cval: f64,
+                    output: &str,
+                ) -> PyResult<PyObject> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        filters::gaussian_laplace::<$t, f64, _>(arr, &sigma, truncate, border)
+                    });
+                    filter_output(py, out, output)
+                }
+
+                m.add_function(wrap_pyfunction!($score, m)?)?;
+                #[pyfunction]
+                fn $score(a: PyReadonlyArrayDyn<$t>, mode: &str, cval: f64) -> PyResult<f64> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    Ok(with_static_dim!(scalar a.to_owned_array(), |arr| filters::laplacian_variance(arr, border)))
+                }
+
+                m.add_function(wrap_pyfunction!($map, m)?)?;
+                #[pyfunction]
+                fn $map<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        filters::laplacian_variance_map(arr, win, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_laplace_filters!(
+            laplace_for_dyn_u8, gaussian_laplace_for_dyn_u8,
+            laplacian_variance_for_dyn_u8, laplacian_variance_map_for_dyn_u8: u8,
+            laplace_for_dyn_u16, gaussian_laplace_for_dyn_u16,
+            laplacian_variance_for_dyn_u16, laplacian_variance_map_for_dyn_u16: u16,
+            laplace_for_dyn_u32, gaussian_laplace_for_dyn_u32,
+            laplacian_variance_for_dyn_u32, laplacian_variance_map_for_dyn_u32: u32,
+            laplace_for_dyn_i8, gaussian_laplace_for_dyn_i8,
+            laplacian_variance_for_dyn_i8, laplacian_variance_map_for_dyn_i8: i8,
+            laplace_for_dyn_i16, gaussian_laplace_for_dyn_i16,
+            laplacian_variance_for_dyn_i16, laplacian_variance_map_for_dyn_i16: i16,
+            laplace_for_dyn_i32, gaussian_laplace_for_dyn_i32,
+            laplacian_variance_for_dyn_i32, laplacian_variance_map_for_dyn_i32: i32,
+            laplace_for_dyn_u64, gaussian_laplace_for_dyn_u64,
+            laplacian_variance_for_dyn_u64, laplacian_variance_map_for_dyn_u64: u64,
+            laplace_for_dyn_i64, gaussian_laplace_for_dyn_i64,
+            laplacian_variance_for_dyn_i64, laplacian_variance_map_for_dyn_i64: i64,
+            laplace_for_dyn_f32, gaussian_laplace_for_dyn_f32,
+            laplacian_variance_for_dyn_f32, laplacian_variance_map_for_dyn_f32: f32,
+            laplace_for_dyn_f64, gaussian_laplace_for_dyn_f64,
+            laplacian_variance_for_dyn_f64, laplacian_variance_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_bilateral_filter {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    sigma_spatial: f64,
+                    sigma_range: f64,
+                    mode: &str,
+                    cval: f64,
+                    rounding: &str,
+                    overflow: &str,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let policy = conversion_policy_from_str(rounding, overflow)?;
+                    if !filters::valid_sigma(sigma_spatial) || !filters::valid_sigma(sigma_range) {
+                        return Err(PyValueError::new_err(format!(
+                            "sigma_spatial and sigma_range must be positive and finite, got {} and {}",
+                            sigma_spatial, sigma_range
+                        )));
+                    }
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        filters::bilateral_filter_converted(arr, win, sigma_spatial, sigma_range, border, policy)
+                            .map_err(|e| PyOverflowError::new_err(e.to_string()))?
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_bilateral_filter!(
+            bilateral_filter_for_dyn_u8: u8,
+            bilateral_filter_for_dyn_u16: u16,
+            bilateral_filter_for_dyn_u32: u32,
+            bilateral_filter_for_dyn_i8: i8,
+            bilateral_filter_for_dyn_i16: i16,
+            bilateral_filter_for_dyn_i32: i32,
+            bilateral_filter_for_dyn_u64: u64,
+            bilateral_filter_for_dyn_i64: i64,
+            bilateral_filter_for_dyn_f32: f32,
+            bilateral_filter_for_dyn_f64: f64
+        );
+
+        macro_rules! add_local_binary_pattern {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    points: usize,
+                    radius: f64,
+                    method: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<u32, IxDyn>> {
+                    let method = lbp_method_from_str(method)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if !(1..=32).contains(&points) || a.ndim() < 2 {
+                        return Err(PyValueError::new_err(
+                            "points must be between 1 and 32 and the array needs at least 2 dimensions",
+                        ));
+                    }
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        texture::local_binary_pattern(arr, points, radius, method, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_local_binary_pattern!(
+            local_binary_pattern_for_dyn_u8: u8,
+            local_binary_pattern_for_dyn_u16: u16,
+            local_binary_pattern_for_dyn_u32: u32,
+            local_binary_pattern_for_dyn_i8: i8,
+            local_binary_pattern_for_dyn_i16: i16,
+            local_binary_pattern_for_dyn_i32: i32,
+            local_binary_pattern_for_dyn_u64: u64,
+            local_binary_pattern_for_dyn_i64: i64,
+            local_binary_pattern_for_dyn_f32: f32,
+            local_binary_pattern_for_dyn_f64: f64
+        );
+
+        macro_rules! add_local_threshold {
+            ($($map_name:ident, $mask_name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($map_name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $map_name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    method: &str,
+                    k: Option<f64>,
+                    r: Option<f64>,
+                    contrast_threshold: f64,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let method = threshold_method_from_str::<$t>(method, k, r, contrast_threshold)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        threshold::threshold_map(arr, win, method, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+
+                m.add_function(wrap_pyfunction!($mask_name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $mask_name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    method: &str,
+                    k: Option<f64>,
+                    r: Option<f64>,
+                    contrast_threshold: f64,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<bool, IxDyn>> {
+                    let method = threshold_method_from_str::<$t>(method, k, r, contrast_threshold)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        threshold::threshold_mask(arr, win, method, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_local_threshold!(
+            threshold_map_for_dyn_u8, threshold_mask_for_dyn_u8: u8,
+            threshold_map_for_dyn_u16, threshold_mask_for_dyn_u16: u16,
+            threshold_map_for_dyn_u32, threshold_mask_for_dyn_u32: u32,
+            threshold_map_for_dyn_i8, threshold_mask_for_dyn_i8: i8,
+            threshold_map_for_dyn_i16, threshold_mask_for_dyn_i16: i16,
+            threshold_map_for_dyn_i32, threshold_mask_for_dyn_i32: i32,
+            threshold_map_for_dyn_u64, threshold_mask_for_dyn_u64: u64,
+            threshold_map_for_dyn_i64, threshold_mask_for_dyn_i64: i64,
+            threshold_map_for_dyn_f32, threshold_mask_for_dyn_f32: f32,
+            threshold_map_for_dyn_f64, threshold_mask_for_dyn_f64: f64
+        );
+
+        macro_rules! add_contrast_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    measure: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let measure = contrast_measure_from_str(measure)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        contrast::contrast_map(arr, WinSh::from_slice(window.as_slice()), measure, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_contrast_map!(
+            contrast_map_for_dyn_u8: u8,
+            contrast_map_for_dyn_u16: u16,
+            contrast_map_for_dyn_u32: u32,
+            contrast_map_for_dyn_i8: i8,
+            contrast_map_for_dyn_i16: i16,
+            contrast_map_for_dyn_i32: i32,
+            contrast_map_for_dyn_u64: u64,
+            contrast_map_for_dyn_i64: i64,
+            contrast_map_for_dyn_f32: f32,
+            contrast_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_peak_local_max {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    min_distance: usize,
+                    threshold: Option<f64>,
+                    strict: bool,
+                    plateau: &str,
+                ) -> PyResult<&'py PyArray<usize, Ix2>> {
+                    let plateau = plateau_from_str(plateau)?;
+                    // the coordinates are always 2d, the dynamic wrapper is undone afterwards
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        peaks::peak_local_max(arr, min_distance, threshold, strict, plateau)
+                    });
+                    let out = out.into_dimensionality::<Ix2>().unwrap();
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_peak_local_max!(
+            peak_local_max_for_dyn_u8: u8,
+            peak_local_max_for_dyn_u16: u16,
+            peak_local_max_for_dyn_u32: u32,
+            peak_local_max_for_dyn_i8: i8,
+            peak_local_max_for_dyn_i16: i16,
+            peak_local_max_for_dyn_i32: i32,
+            peak_local_max_for_dyn_u64: u64,
+            peak_local_max_for_dyn_i64: i64,
+            peak_local_max_for_dyn_f32: f32,
+            peak_local_max_for_dyn_f64: f64
+        );
+
+        macro_rules! add_extremum_offset {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    extremum: &str,
+                    per_axis: bool,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<(&'py PyArray<$t, IxDyn>, &'py PyArray<usize, IxDyn>)> {
+                    let extremum = extremum_from_str(extremum)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    // assigned in every dimension branch of the macro
+                    let values;
+                    let offsets = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        let (v, offsets) = extrema::extremum_offset_map(arr, win, extremum, border);
+                        values = v.into_dyn();
+                        match per_axis {
+                            true => extrema::unravel_offsets(&offsets, win).into_dyn(),
+                            false => offsets.into_dyn(),
+                        }
+                    });
+                    Ok((values.to_pyarray(py), offsets.to_pyarray(py)))
+                }
+            )*};
+        }
+        add_extremum_offset!(
+            extremum_offset_for_dyn_u8: u8,
+            extremum_offset_for_dyn_u16: u16,
+            extremum_offset_for_dyn_u32: u32,
+            extremum_offset_for_dyn_i8: i8,
+            extremum_offset_for_dyn_i16: i16,
+            extremum_offset_for_dyn_i32: i32,
+            extremum_offset_for_dyn_u64: u64,
+            extremum_offset_for_dyn_i64: i64,
+            extremum_offset_for_dyn_f32: f32,
+            extremum_offset_for_dyn_f64: f64
+        );
+
+        macro_rules! add_moment_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    moment: &str,
+                    order: u32,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let moment = moment_from_str(moment, order)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        statistics::moment_map(arr, WinSh::from_slice(window.as_slice()), moment, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_moment_map!(
+            moment_map_for_dyn_u8: u8,
+            moment_map_for_dyn_u16: u16,
+            moment_map_for_dyn_u32: u32,
+            moment_map_for_dyn_i8: i8,
+            moment_map_for_dyn_i16: i16,
+            moment_map_for_dyn_i32: i32,
+            moment_map_for_dyn_u64: u64,
+            moment_map_for_dyn_i64: i64,
+            moment_map_for_dyn_f32: f32,
+            moment_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_nan_reduce_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    reduction: &str,
+                    ddof: usize,
+                    nan_policy: &str,
+                    min_count: usize,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let reduction = nan_reduction_from_str(reduction, ddof)?;
+                    let policy = nan_policy_from_str(nan_policy, min_count)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        statistics::nan_reduce_map(arr, WinSh::from_slice(window.as_slice()), reduction, policy, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_nan_reduce_map!(
+            nan_reduce_map_for_dyn_f32: f32,
+            nan_reduce_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_robust_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    statistic: &str,
+                    alpha: f64,
+                    scaled: bool,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let statistic = robust_statistic_from_str(statistic, alpha, scaled)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        statistics::robust_map(arr, WinSh::from_slice(window.as_slice()), statistic, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_robust_map!(
+            robust_map_for_dyn_u8: u8,
+            robust_map_for_dyn_u16: u16,
+            robust_map_for_dyn_u32: u32,
+            robust_map_for_dyn_i8: i8,
+            robust_map_for_dyn_i16: i16,
+            robust_map_for_dyn_i32: i32,
+            robust_map_for_dyn_u64: u64,
+            robust_map_for_dyn_i64: i64,
+            robust_map_for_dyn_f32: f32,
+            robust_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_categorical_filters {
+            ($($majority:ident, $minority:ident, $variety:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($majority, m)?)?;
+                #[pyfunction]
+                fn $majority<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    tie: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let tie = tie_break_from_str(tie)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        categorical::majority_filter(arr, WinSh::from_slice(window.as_slice()), tie, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+
+                m.add_function(wrap_pyfunction!($minority, m)?)?;
+                #[pyfunction]
+                fn $minority<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    tie: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<$t, IxDyn>> {
+                    let tie = tie_break_from_str(tie)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        categorical::minority_filter(arr, WinSh::from_slice(window.as_slice()), tie, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+
+                m.add_function(wrap_pyfunction!($variety, m)?)?;
+                #[pyfunction]
+                fn $variety<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<usize, IxDyn>> {
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        categorical::variety_filter(arr, WinSh::from_slice(window.as_slice()), border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_categorical_filters!(
+            majority_filter_for_dyn_u8, minority_filter_for_dyn_u8, variety_filter_for_dyn_u8: u8,
+            majority_filter_for_dyn_u16, minority_filter_for_dyn_u16, variety_filter_for_dyn_u16: u16,
+            majority_filter_for_dyn_u32, minority_filter_for_dyn_u32, variety_filter_for_dyn_u32: u32,
+            majority_filter_for_dyn_i8, minority_filter_for_dyn_i8, variety_filter_for_dyn_i8: i8,
+            majority_filter_for_dyn_i16, minority_filter_for_dyn_i16, variety_filter_for_dyn_i16: i16,
+            majority_filter_for_dyn_i32, minority_filter_for_dyn_i32, variety_filter_for_dyn_i32: i32,
+            majority_filter_for_dyn_u64, minority_filter_for_dyn_u64, variety_filter_for_dyn_u64: u64,
+            majority_filter_for_dyn_i64, minority_filter_for_dyn_i64, variety_filter_for_dyn_i64: i64,
+            majority_filter_for_dyn_f32, minority_filter_for_dyn_f32, variety_filter_for_dyn_f32: f32,
+            majority_filter_for_dyn_f64, minority_filter_for_dyn_f64, variety_filter_for_dyn_f64: f64
+        );
+
+        macro_rules! add_glcm_features {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    offsets: Vec<Vec<isize>>,
+                    levels: usize,
+                    features: Vec<String>,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let features = glcm_features_from_str(&features)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if !(1..=1 << 16).contains(&levels) || offsets.iter().any(|o| o.len() != a.ndim()) {
+                        return Err(PyValueError::new_err(
+                            "levels must be between 1 and 65536 and every offset needs one step per axis",
+                        ));
+                    }
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let win = WinSh::from_slice(window.as_slice());
+                        texture::glcm_features(arr, win, &offsets, levels, &features, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_glcm_features!(
+            glcm_features_for_dyn_u8: u8,
+            glcm_features_for_dyn_u16: u16,
+            glcm_features_for_dyn_u32: u32,
+            glcm_features_for_dyn_i8: i8,
+            glcm_features_for_dyn_i16: i16,
+            glcm_features_for_dyn_i32: i32,
+            glcm_features_for_dyn_u64: u64,
+            glcm_features_for_dyn_i64: i64,
+            glcm_features_for_dyn_f32: f32,
+            glcm_features_for_dyn_f64: f64
+        );
+
+        macro_rules! add_pair_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    b: PyReadonlyArrayDyn<$t>,
+                    window: Vec<usize>,
+                    statistic: &str,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let statistic = pair_statistic_from_str(statistic)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if a.shape() != b.shape() {
+                        return Err(PyValueError::new_err("both arrays must have the same shape"));
+                    }
+                    let b = b.to_owned_array();
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let other = b.into_shape(arr.raw_dim()).unwrap();
+                        paired::pair_map(arr, other, WinSh::from_slice(window.as_slice()), statistic, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_pair_map!(
+            pair_map_for_dyn_u8: u8,
+            pair_map_for_dyn_u16: u16,
+            pair_map_for_dyn_u32: u32,
+            pair_map_for_dyn_i8: i8,
+            pair_map_for_dyn_i16: i16,
+            pair_map_for_dyn_i32: i32,
+            pair_map_for_dyn_u64: u64,
+            pair_map_for_dyn_i64: i64,
+            pair_map_for_dyn_f32: f32,
+            pair_map_for_dyn_f64: f64
+        );
+
+        macro_rules! add_match_template {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    image: PyReadonlyArrayDyn<$t>,
+                    template: PyReadonlyArrayDyn<$t>,
+                    score: &str,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let score = template_score_from_str(score)?;
+                    if image.ndim() != template.ndim()
+                        || image.shape().iter().zip(template.shape()).any(|(i, t)| t > i || *t == 0)
+                    {
+                        return Err(PyValueError::new_err(
+                            "the template needs the same number of dimensions as the image and must fit inside it",
+                        ));
+                    }
+                    let template = template.to_owned_array();
+                    let out = with_static_dim!(image.to_owned_array(), |arr| {
+                        let mut template_dim = arr.raw_dim();
+                        template_dim.slice_mut().copy_from_slice(template.shape());
+                        let template = template.into_shape(template_dim).unwrap();
+                        template::match_template(arr, template, score)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_match_template!(
+            match_template_for_dyn_u8: u8,
+            match_template_for_dyn_u16: u16,
+            match_template_for_dyn_u32: u32,
+            match_template_for_dyn_i8: i8,
+            match_template_for_dyn_i16: i16,
+            match_template_for_dyn_i32: i32,
+            match_template_for_dyn_u64: u64,
+            match_template_for_dyn_i64: i64,
+            match_template_for_dyn_f32: f32,
+            match_template_for_dyn_f64: f64
+        );
+
+        m.add_function(wrap_pyfunction!(best_matches, m)?)?;
+        #[pyfunction]
+        fn best_matches<'py>(
+            py: Python<'py>,
+            scores: PyReadonlyArrayDyn<f64>,
+            score: &str,
+            count: usize,
+            min_distance: usize,
+        ) -> PyResult<&'py PyArray<usize, Ix2>> {
+            let score = template_score_from_str(score)?;
+            let out = with_static_dim!(scores.to_owned_array(), |arr| {
+                template::best_matches(&arr, score, count, min_distance)
+            });
+            Ok(out.into_dimensionality::<Ix2>().unwrap().to_pyarray(py))
+        }
+
+        macro_rules! add_ssim_map {
+            ($($name:ident: $t:ty),*) => {$(
+                m.add_function(wrap_pyfunction!($name, m)?)?;
+                #[pyfunction]
+                #[allow(clippy::too_many_arguments)]
+                fn $name<'py>(
+                    py: Python<'py>,
+                    a: PyReadonlyArrayDyn<$t>,
+                    b: PyReadonlyArrayDyn<$t>,
+                    window: &str,
+                    sigma: f64,
+                    size: usize,
+                    k1: f64,
+                    k2: f64,
+                    mode: &str,
+                    cval: f64,
+                ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+                    let params = ssim_params_from(window, sigma, size, k1, k2)?;
+                    let border = border_from_str::<$t>(mode, cval)?;
+                    if a.shape() != b.shape() {
+                        return Err(PyValueError::new_err("both arrays must have the same shape"));
+                    }
+                    let b = b.to_owned_array();
+                    let out = with_static_dim!(a.to_owned_array(), |arr| {
+                        let other = b.into_shape(arr.raw_dim()).unwrap();
+                        similarity::ssim_map(arr, other, &params, border)
+                    });
+                    Ok(out.to_pyarray(py))
+                }
+            )*};
+        }
+        add_ssim_map!(
+            ssim_map_for_dyn_u8: u8,
+            ssim_map_for_dyn_u16: u16,
+            ssim_map_for_dyn_u32: u32,
+            ssim_map_for_dyn_i8: i8,
+            ssim_map_for_dyn_i16: i16,
+            ssim_map_for_dyn_i32: i32,
+            ssim_map_for_dyn_u64: u64,
+            ssim_map_for_dyn_i64: i64,
+            ssim_map_for_dyn_f32: f32,
+            ssim_map_for_dyn_f64: f64
+        );
+
+        m.add_function(wrap_pyfunction!(ssim_map_float, m)?)?;
+        #[pyfunction]
+        #[allow(clippy::too_many_arguments)]
+        fn ssim_map_float<'py>(
+            py: Python<'py>,
+            a: PyReadonlyArrayDyn<f64>,
+            b: PyReadonlyArrayDyn<f64>,
+            data_range: f64,
+            window: &str,
+            sigma: f64,
+            size: usize,
+            k1: f64,
+            k2: f64,
+            mode: &str,
+            cval: f64,
+        ) -> PyResult<&'py PyArray<f64, IxDyn>> {
+            let params = ssim_params_from(window, sigma, size, k1, k2)?;
+            let border = border_from_str::<i32>(mode, 0f64)?.map_constant(|_| cval);
+            if a.shape() != b.shape() {
+                return Err(PyValueError::new_err("both arrays must have the same shape"));
+            }
+            let b = b.to_owned_array();
+            let out = with_static_dim!(a.to_owned_array(), |arr| {
+                let other = b.into_shape(arr.raw_dim()).unwrap();
+                similarity::ssim_map_f64(arr, other, data_range, &params, border)
+            });
+            Ok(out.to_pyarray(py))
+        }
+
+        m.add_function(wrap_pyfunction!(lbp_histogram, m)?)?;
+        #[pyfunction]
+        fn lbp_histogram<'py>(
+            py: Python<'py>,
+            labels: PyReadonlyArrayDyn<u32>,
+            window: Vec<usize>,
+            bins: usize,
+        ) -> &'py PyArray<u32, IxDyn> {
+            with_static_dim!(labels.to_owned_array(), |arr| {
+                texture::lbp_histogram(arr, WinSh::from_slice(window.as_slice()), bins)
+            })
+            .to_pyarray(py)
+        }
 
         Ok(())
     }
@@ -265,6 +1724,12 @@ mod python_lib {
             apply_func(arr.as_array(), window_functions::func_fast_sample_std).into_py(py)
         }
 
+        m.add_function(wrap_pyfunction!(func_michelson_contrast, m)?)?;
+        #[pyfunction]
+        fn func_michelson_contrast(py: Python, arr: PyReadonlyArray1<u8>) -> PyObject {
+            apply_func(arr.as_array(), window_functions::func_michelson_contrast).into_py(py)
+        }
+
         Ok(())
     }
 }
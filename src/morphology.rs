@@ -0,0 +1,324 @@
+use ndarray::{Array, ArrayView, Dimension, RemoveAxis, Slice};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{centre_crop, centred_padding, pad_array, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+
+/// flat rectangular structuring element, every element of the window is used
+///
+/// equivalent to using [crate::window_functions::func_window_min] / [crate::window_functions::func_window_max]
+pub fn rectangle_footprint<D: Dimension>(win_size: D) -> Array<bool, D> {
+    Array::from_elem(win_size, true)
+}
+
+/// minimum of the window, only elements where `footprint` is `true` are used
+#[inline]
+pub fn footprint_min<T, D>(w: ArrayView<T, D>, footprint: &Array<bool, D>) -> T
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    w.iter()
+        .zip(footprint.iter())
+        .fold(T::MAX, |a, (&x, &m)| if m && x < a { x } else { a })
+}
+
+/// maximum of the window, only elements where `footprint` is `true` are used
+#[inline]
+pub fn footprint_max<T, D>(w: ArrayView<T, D>, footprint: &Array<bool, D>) -> T
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    w.iter()
+        .zip(footprint.iter())
+        .fold(T::MIN, |a, (&x, &m)| if m && x > a { x } else { a })
+}
+
+/// `a - b` saturated to the range of `T`, integers are subtracted exactly
+#[inline]
+fn saturating_difference<T: WinNum>(a: T, b: T) -> T {
+    match (a.as_i128(), b.as_i128()) {
+        (Some(a), Some(b)) => match a.checked_sub(b) {
+            Some(d) => <T as num_traits::NumCast>::from(d).unwrap_or(if d > 0 { T::MAX } else { T::MIN }),
+            None if a > b => T::MAX,
+            None => T::MIN,
+        },
+        _ => T::from_f64(a.as_f64() - b.as_f64()),
+    }
+}
+
+/// footprint mirrored on every axis, dilation uses the reflected structuring element
+fn reflect_footprint<D: Dimension>(footprint: &Array<bool, D>) -> Array<bool, D> {
+    footprint
+        .slice_each_axis(|_| Slice::new(0, None, -1))
+        .to_owned()
+}
+
+/// pad `arr` for `border` then run `func` over every window on all threads
+fn thread_with_border<T, O, D, F>(
+    arr: Array<T, D>,
+    win_size: D,
+    (before, after): (D, D),
+    border: &BorderMode<T>,
+    func: F,
+) -> Array<O, D>
+where
+    T: Clone + Send + 'static,
+    O: Clone + Send + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+    F: Fn(ArrayView<T, D>) -> O + Send + Sync + 'static,
+{
+    let arr = match border {
+        BorderMode::Valid => arr,
+        _ => pad_array(&arr, &before, &after, border),
+    };
+    thread_over_any_window_map(arr, win_size, func)
+}
+
+/// grey-scale erosion, the minimum over the footprint centred on each element
+///
+/// with a [rectangle_footprint] and [BorderMode::Valid] this is the same as
+/// `thread_over_any_window(arr, win_size, func_window_min)`
+pub fn grey_erosion<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let win_size = footprint.raw_dim();
+    let fp = footprint.clone();
+    thread_with_border(arr, win_size, centred_padding(&win_size), &border, move |w| {
+        footprint_min(w, &fp)
+    })
+}
+
+/// grey-scale dilation, the maximum over the reflected footprint centred on each element
+///
+/// with a [rectangle_footprint] and [BorderMode::Valid] this is the same as
+/// `thread_over_any_window(arr, win_size, func_window_max)`
+pub fn grey_dilation<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let win_size = footprint.raw_dim();
+    let fp = reflect_footprint(footprint);
+    // the reflected footprint is centred on `window - 1 - window / 2`
+    let (before, after) = centred_padding(&win_size);
+    thread_with_border(arr, win_size, (after, before), &border, move |w| {
+        footprint_max(w, &fp)
+    })
+}
+
+/// erosion followed by dilation, removes bright details smaller than the footprint
+///
+/// with [BorderMode::Valid] the output shrinks by `2 * (window - 1)` on every axis
+pub fn grey_opening<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    grey_dilation(grey_erosion(arr, footprint, border), footprint, border)
+}
+
+/// dilation followed by erosion, removes dark details smaller than the footprint
+///
+/// with [BorderMode::Valid] the output shrinks by `2 * (window - 1)` on every axis
+pub fn grey_closing<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    grey_erosion(grey_dilation(arr, footprint, border), footprint, border)
+}
+
+/// [grey_dilation] minus [grey_erosion], the dilation uses the reflected footprint so
+/// asymmetric footprints are handled the same as the two separate operations
+pub fn morphological_gradient<T, D>(
+    arr: Array<T, D>,
+    footprint: &Array<bool, D>,
+    border: BorderMode<T>,
+) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let dilated = grey_dilation(arr.clone(), footprint, border);
+    let eroded = grey_erosion(arr, footprint, border);
+    ndarray::Zip::from(&dilated)
+        .and(&eroded)
+        .map_collect(|&d, &e| saturating_difference(d, e))
+}
+
+/// input minus its opening, keeps bright details smaller than the footprint
+///
+/// with [BorderMode::Valid] the input is cropped to line up with the opening
+pub fn white_tophat<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let opened = grey_opening(arr.clone(), footprint, border);
+    let arr = centre_crop(&arr, &opened.raw_dim());
+    ndarray::Zip::from(&arr)
+        .and(&opened)
+        .map_collect(|&a, &o| saturating_difference(a, o))
+}
+
+/// closing minus the input, keeps dark details smaller than the footprint
+///
+/// with [BorderMode::Valid] the input is cropped to line up with the closing
+pub fn black_tophat<T, D>(arr: Array<T, D>, footprint: &Array<bool, D>, border: BorderMode<T>) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let closed = grey_closing(arr.clone(), footprint, border);
+    let arr = centre_crop(&arr, &closed.raw_dim());
+    ndarray::Zip::from(&arr)
+        .and(&closed)
+        .map_collect(|&a, &c| saturating_difference(c, a))
+}
+
+/// unconstrained grey-scale hit-or-miss transform
+///
+/// each output is `min(window[hit]) - max(window[miss])`, or zero when that is negative,
+/// on a binary image this is non zero only where the `hit` elements are all foreground
+/// and the `miss` elements are all background.
+/// An empty `miss` footprint makes this the same as [grey_erosion]
+///
+/// # panics
+/// if `hit` and `miss` don't have the same shape
+pub fn hit_or_miss<T, D>(
+    arr: Array<T, D>,
+    hit: &Array<bool, D>,
+    miss: &Array<bool, D>,
+    border: BorderMode<T>,
+) -> Array<T, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    assert_eq!(hit.shape(), miss.shape(), "hit and miss footprints must have the same shape");
+    let win_size = hit.raw_dim();
+    let (hit, miss) = (hit.clone(), miss.clone());
+    let has_miss = miss.iter().any(|&m| m);
+    thread_with_border(arr, win_size, centred_padding(&win_size), &border, move |w| {
+        let fg = footprint_min(w.view(), &hit);
+        if !has_miss {
+            return fg;
+        }
+        let bg = footprint_max(w, &miss);
+        if fg > bg {
+            saturating_difference(fg, bg)
+        } else {
+            T::zero()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array2, Ix2};
+
+    use crate::array_threading::apply_over_any_window;
+    use crate::window_functions::{func_window_max, func_window_min};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn rectangle_matches_window_functions() {
+        let a: Array2<u8> = Array2::from_shape_fn((40, 30), not_a_hash);
+        let fp = rectangle_footprint(Ix2(3, 4));
+        assert_eq!(
+            grey_erosion(a.clone(), &fp, BorderMode::Valid),
+            apply_over_any_window(a.clone(), Ix2(3, 4), func_window_min)
+        );
+        assert_eq!(
+            grey_dilation(a.clone(), &fp, BorderMode::Valid),
+            apply_over_any_window(a, Ix2(3, 4), func_window_max)
+        );
+    }
+
+    #[test]
+    fn opening_removes_small_peaks() {
+        let a = arr1(&[1u8, 1, 9, 1, 1, 5, 5, 5, 1, 1]);
+        let fp = rectangle_footprint(ndarray::Ix1(3));
+        let opened = grey_opening(a.clone(), &fp, BorderMode::Nearest);
+        assert_eq!(opened, arr1(&[1, 1, 1, 1, 1, 5, 5, 5, 1, 1]));
+        let tophat = white_tophat(a, &fp, BorderMode::Nearest);
+        assert_eq!(tophat, arr1(&[0, 0, 8, 0, 0, 0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn closing_fills_small_pits() {
+        let a = arr1(&[5u8, 5, 0, 5, 5, 5]);
+        let fp = rectangle_footprint(ndarray::Ix1(3));
+        assert_eq!(grey_closing(a.clone(), &fp, BorderMode::Nearest), arr1(&[5, 5, 5, 5, 5, 5]));
+        assert_eq!(black_tophat(a, &fp, BorderMode::Nearest), arr1(&[0, 0, 5, 0, 0, 0]));
+    }
+
+    #[test]
+    fn valid_opening_lines_up() {
+        let a: Array2<u8> = Array2::from_shape_fn((20, 25), not_a_hash);
+        let fp = rectangle_footprint(Ix2(3, 3));
+        let valid = white_tophat(a.clone(), &fp, BorderMode::Valid);
+        let same = white_tophat(a, &fp, BorderMode::Nearest);
+        assert_eq!(valid.shape(), &[16, 21]);
+        // away from the border the padding can't change anything
+        assert_eq!(valid, same.slice(ndarray::s![2..18, 2..23]));
+    }
+
+    #[test]
+    fn gradient_is_range() {
+        let a: Array2<u8> = Array2::from_shape_fn((20, 25), not_a_hash);
+        let fp = rectangle_footprint(Ix2(3, 3));
+        let grad = morphological_gradient(a.clone(), &fp, BorderMode::Valid);
+        let dil = grey_dilation(a.clone(), &fp, BorderMode::Valid);
+        let ero = grey_erosion(a, &fp, BorderMode::Valid);
+        assert_eq!(grad, &dil - &ero);
+    }
+
+    #[test]
+    fn asymmetric_gradient_matches_composition() {
+        let a: Array2<u8> = Array2::from_shape_fn((20, 25), not_a_hash);
+        let fp = arr2(&[[true, true, false], [false, true, false], [false, false, true], [true, false, false]]);
+        for border in [BorderMode::Nearest, BorderMode::Constant(7)] {
+            let grad = morphological_gradient(a.clone(), &fp, border);
+            let dil = grey_dilation(a.clone(), &fp, border);
+            let ero = grey_erosion(a.clone(), &fp, border);
+            // the footprint doesn't contain its centre, so the dilation can be below the erosion
+            let expected = ndarray::Zip::from(&dil).and(&ero).map_collect(|d, e| d.saturating_sub(*e));
+            assert_eq!(grad, expected);
+        }
+    }
+
+    #[test]
+    fn float_erosion_and_dilation() {
+        let a = arr1(&[0.5f32, -1.0, 2.0, 0.0, f32::INFINITY]);
+        let fp = rectangle_footprint(ndarray::Ix1(3));
+        assert_eq!(grey_erosion(a.clone(), &fp, BorderMode::Nearest), arr1(&[-1.0, -1.0, -1.0, 0.0, 0.0]));
+        assert_eq!(grey_dilation(a.clone(), &fp, BorderMode::Nearest), arr1(&[0.5, 2.0, 2.0, f32::INFINITY, f32::INFINITY]));
+        let grad = morphological_gradient(a, &fp, BorderMode::Nearest);
+        assert_eq!(grad, arr1(&[1.5, 3.0, 3.0, f32::INFINITY, f32::INFINITY]));
+    }
+
+    #[test]
+    fn hit_or_miss_finds_isolated_points() {
+        let a = arr2(&[
+            [0u8, 0, 0, 0, 0],
+            [0, 1, 0, 1, 1],
+            [0, 0, 0, 0, 0],
+        ]);
+        let hit = arr2(&[[false, false, false], [false, true, false], [false, false, false]]);
+        let miss = hit.mapv(|h| !h);
+        let out = hit_or_miss(a, &hit, &miss, BorderMode::Constant(0));
+        assert_eq!(
+            out,
+            arr2(&[[0, 0, 0, 0, 0], [0, 1, 0, 0, 0], [0, 0, 0, 0, 0]])
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use ndarray::{Array, Dimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_windows_lockstep;
+use crate::border_handling::{pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+use crate::window_functions::{window_covariance, window_pearson_correlation, window_weighted_mean};
+
+/// statistic of two aligned windows, calculated by [pair_map]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PairStatistic {
+    /// see [window_pearson_correlation]
+    Correlation,
+    /// see [window_covariance]
+    Covariance,
+    /// mean of the first array weighted by the second, see [window_weighted_mean]
+    WeightedMean,
+}
+
+/// statistic of the windows centred on every element of two arrays with the same shape,
+/// the same window is slid over both arrays in lockstep with [thread_over_windows_lockstep]
+///
+/// both arrays are padded with `border`, with [BorderMode::Valid] the output shrinks by
+/// `window - 1`
+///
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let a: Array2<u8> = Array2::from_shape_fn((6, 6), |(r, c)| (r * c) as u8);
+/// let r = pair_map(a.clone(), a, Ix2(3, 3), PairStatistic::Correlation, BorderMode::Valid);
+/// assert!(r.iter().all(|&x| (x - 1.0).abs() < 1e-12 || x == 0.0));
+/// ```
+pub fn pair_map<T, D>(a: Array<T, D>, b: Array<T, D>, win_size: D, statistic: PairStatistic, border: BorderMode<T>) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arrays = [pad_for_window(a, &win_size, border), pad_for_window(b, &win_size, border)];
+    match statistic {
+        PairStatistic::Correlation => thread_over_windows_lockstep(arrays, win_size, window_pearson_correlation),
+        PairStatistic::Covariance => thread_over_windows_lockstep(arrays, win_size, window_covariance),
+        PairStatistic::WeightedMean => thread_over_windows_lockstep(arrays, win_size, window_weighted_mean),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, Array2, Ix1, Ix2};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn reference_formulas() {
+        let a: Array2<i16> = Array2::from_shape_fn((19, 14), |ix| not_a_hash(ix) as i16 * 2 - 256);
+        let b: Array2<i16> = Array2::from_shape_fn((19, 14), |(r, c)| (not_a_hash((c + 3, r + 1)) as i16 * 2 - 256) / 3 + 7);
+        let win = Ix2(5, 3);
+        let corr = pair_map(a.clone(), b.clone(), win, PairStatistic::Correlation, BorderMode::Valid);
+        let cov = pair_map(a.clone(), b.clone(), win, PairStatistic::Covariance, BorderMode::Valid);
+        for (((r, c), wa), wb) in corr.indexed_iter().map(|(ix, _)| ix).zip(a.windows(win)).zip(b.windows(win)) {
+            let (x, y) = (wa.mapv(|v| v as f64), wb.mapv(|v| v as f64));
+            let (mx, my) = (x.mean().unwrap(), y.mean().unwrap());
+            let expected_cov = ((&x - mx) * (&y - my)).mean().unwrap();
+            assert!((cov[[r, c]] - expected_cov).abs() < 1e-9);
+            let (sx, sy) = (x.std(0.0), y.std(0.0));
+            let expected_corr = if sx == 0.0 || sy == 0.0 { 0.0 } else { expected_cov / (sx * sy) };
+            assert!((corr[[r, c]] - expected_corr).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn weighted_mean() {
+        let values = arr1(&[10u8, 20, 30, 40]);
+        let weights = arr1(&[1u8, 0, 3, 0]);
+        let m = pair_map(values, weights, Ix1(3), PairStatistic::WeightedMean, BorderMode::Valid);
+        assert_eq!(m, arr1(&[25.0, 30.0]));
+        let flat = pair_map(arr1(&[1u8, 2, 3]), arr1(&[0u8, 0, 0]), Ix1(3), PairStatistic::WeightedMean, BorderMode::Reflect);
+        assert_eq!(flat, arr1(&[0.0, 0.0, 0.0]));
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::VecDeque;
+
+use ndarray::{Array, Array2, Dimension, IntoDimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{pad_array, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+
+/// how connected elements with the same value (a flat topped peak) are reported
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Plateau {
+    /// every element of the plateau is a peak
+    All,
+    /// only the first element of the plateau in logical (row major) order
+    First,
+    /// only the element closest to the centroid of the plateau, ties go to the first in logical order
+    Centre,
+}
+
+/// coordinates of the local maxima of `arr`, one row per peak, sorted by value (highest first)
+/// then by position in logical order
+///
+/// - an element is a peak when it is the maximum of the `2 * min_distance + 1` window centred on it,
+///   windows hanging over the edge only use the elements inside the array
+/// - `strict` peaks must be greater than every other element in the window,
+///   otherwise equal neighbours are allowed and [Plateau] decides which of them are kept
+/// - `threshold` drops peaks which are not greater than it
+/// - peaks within `min_distance` (largest distance along any axis) of a higher, or earlier equal,
+///   peak are dropped, `min_distance` of `0` compares the direct neighbours and keeps every peak
+///
+/// the window test is threaded with [thread_over_any_window_map], plateaus and the distance
+/// check run afterwards on the whole array, so the result does not depend on the thread count
+///
+/// ```
+/// use ndarray::arr1;
+/// let a = arr1(&[0u8, 5, 5, 5, 0, 3, 0]);
+/// let peaks = peak_local_max(a, 0, None, false, Plateau::Centre);
+/// assert_eq!(peaks.column(0).to_vec(), vec![2, 5]);
+/// ```
+pub fn peak_local_max<T, D>(
+    arr: Array<T, D>,
+    min_distance: usize,
+    threshold: Option<f64>,
+    strict: bool,
+    plateau: Plateau,
+) -> Array2<usize>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let radius = min_distance.max(1);
+    let mut win_size = arr.raw_dim();
+    let mut pad = arr.raw_dim();
+    for ax in 0..win_size.ndim() {
+        win_size[ax] = 2 * radius + 1;
+        pad[ax] = radius;
+    }
+    // every axis is odd and centred so the centre is the middle element in logical order
+    let centre = win_size.size() / 2;
+    let padded = pad_array(&arr.mapv(Some), &pad, &pad, &BorderMode::Constant(None));
+    let candidates = thread_over_any_window_map(padded, win_size, move |w| {
+        let c = w.iter().nth(centre).copied().flatten().unwrap();
+        threshold.is_none_or(|t| c.as_f64() > t)
+            && w.iter().enumerate().all(|(i, x)| match x {
+                Some(x) if strict && i != centre => *x < c,
+                Some(x) => *x <= c,
+                None => true,
+            })
+    });
+
+    let mut peaks = match (strict, plateau) {
+        (true, _) | (false, Plateau::All) => candidates
+            .indexed_iter()
+            .filter(|(_, &p)| p)
+            .map(|(ix, _)| ix.into_dimension())
+            .collect(),
+        (false, _) => reduce_plateaus(&arr, candidates, plateau),
+    };
+
+    // stable sort, equal values stay in logical order
+    peaks.sort_by(|a, b| arr[*b].total_order(&arr[*a]));
+    if min_distance > 0 {
+        let mut kept: Vec<D> = Vec::with_capacity(peaks.len());
+        for p in peaks {
+            if kept.iter().all(|k| chebyshev(k, &p) > min_distance) {
+                kept.push(p);
+            }
+        }
+        peaks = kept;
+    }
+
+    let ndim = arr.ndim();
+    Array2::from_shape_fn((peaks.len(), ndim), |(i, ax)| peaks[i][ax])
+}
+
+fn chebyshev<D: Dimension>(a: &D, b: &D) -> usize {
+    a.slice()
+        .iter()
+        .zip(b.slice())
+        .fold(0, |m, (&x, &y)| m.max(x.abs_diff(y)))
+}
+
+/// group connected candidates with the same value and keep one element of each group
+fn reduce_plateaus<T, D>(arr: &Array<T, D>, mut candidates: Array<bool, D>, plateau: Plateau) -> Vec<D>
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let shape = arr.raw_dim();
+    let mut neighbour_shape = shape.clone();
+    for ax in 0..shape.ndim() {
+        neighbour_shape[ax] = 3;
+    }
+    let offsets: Vec<D> = ndarray::indices(neighbour_shape)
+        .into_iter()
+        .map(|ix| ix.into_dimension())
+        .filter(|ix: &D| ix.slice().iter().any(|&o| o != 1))
+        .collect();
+
+    let starts: Vec<D> = candidates
+        .indexed_iter()
+        .filter(|(_, &p)| p)
+        .map(|(ix, _)| ix.into_dimension())
+        .collect();
+    let mut peaks = vec![];
+    for start in starts {
+        if !candidates[start.clone()] {
+            continue;
+        }
+        // breadth first fill, clearing candidates as they are visited
+        let value = arr[start.clone()];
+        candidates[start.clone()] = false;
+        let mut group = vec![];
+        let mut queue = VecDeque::from([start]);
+        while let Some(ix) = queue.pop_front() {
+            for offset in offsets.iter() {
+                let mut nb = ix.clone();
+                let inside = (0..shape.ndim()).all(|ax| {
+                    let pos = (ix[ax] + offset[ax]).checked_sub(1);
+                    pos.is_some_and(|p| {
+                        nb[ax] = p;
+                        p < shape[ax]
+                    })
+                });
+                if inside && candidates[nb.clone()] && arr[nb.clone()] == value {
+                    candidates[nb.clone()] = false;
+                    queue.push_back(nb);
+                }
+            }
+            group.push(ix);
+        }
+        peaks.push(match plateau {
+            Plateau::Centre => group_centre(group),
+            _ => group.into_iter().min_by(|a, b| a.slice().cmp(b.slice())).unwrap(),
+        });
+    }
+    peaks
+}
+
+fn group_centre<D: Dimension>(group: Vec<D>) -> D {
+    let n = group.len() as f64;
+    let ndim = group[0].ndim();
+    let centroid: Vec<f64> = (0..ndim)
+        .map(|ax| group.iter().map(|ix| ix[ax] as f64).sum::<f64>() / n)
+        .collect();
+    let distance = |ix: &D| -> f64 { (0..ndim).map(|ax| (ix[ax] as f64 - centroid[ax]).powi(2)).sum() };
+    group
+        .into_iter()
+        .map(|ix| (distance(&ix), ix))
+        .min_by(|(da, a), (db, b)| da.total_cmp(db).then_with(|| a.slice().cmp(b.slice())))
+        .unwrap()
+        .1
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array2};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    fn rows(peaks: &Array2<usize>) -> Vec<Vec<usize>> {
+        peaks.outer_iter().map(|r| r.to_vec()).collect()
+    }
+
+    #[test]
+    fn plateaus_and_strict() {
+        let a = arr1(&[0u8, 5, 5, 5, 0, 3, 0]);
+        let all = peak_local_max(a.clone(), 0, None, false, Plateau::All);
+        assert_eq!(rows(&all), vec![vec![1], vec![2], vec![3], vec![5]]);
+        let first = peak_local_max(a.clone(), 0, None, false, Plateau::First);
+        assert_eq!(rows(&first), vec![vec![1], vec![5]]);
+        let strict = peak_local_max(a.clone(), 0, None, true, Plateau::All);
+        assert_eq!(rows(&strict), vec![vec![5]]);
+        let above = peak_local_max(a, 0, Some(3.0), false, Plateau::Centre);
+        assert_eq!(rows(&above), vec![vec![2]]);
+    }
+
+    #[test]
+    fn min_distance_keeps_highest() {
+        let mut a = Array2::<u16>::zeros((9, 9));
+        a[[2, 2]] = 10;
+        a[[2, 5]] = 20;
+        a[[7, 7]] = 5;
+        let near = peak_local_max(a.clone(), 1, None, true, Plateau::All);
+        assert_eq!(rows(&near), vec![vec![2, 5], vec![2, 2], vec![7, 7]]);
+        let far = peak_local_max(a, 3, None, true, Plateau::All);
+        assert_eq!(rows(&far), vec![vec![2, 5], vec![7, 7]]);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let a: Array2<u8> = Array2::from_shape_fn((41, 37), not_a_hash);
+        let peaks = peak_local_max(a.clone(), 0, None, false, Plateau::All);
+        let mut expected = vec![];
+        for ((r, c), &x) in a.indexed_iter() {
+            let is_peak = (r.saturating_sub(1)..(r + 2).min(41))
+                .all(|i| (c.saturating_sub(1)..(c + 2).min(37)).all(|j| a[[i, j]] <= x));
+            if is_peak {
+                expected.push((x, vec![r, c]));
+            }
+        }
+        expected.sort_by_key(|(x, _)| std::cmp::Reverse(*x));
+        assert_eq!(rows(&peaks), expected.into_iter().map(|(_, p)| p).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn plateau_centre_2d() {
+        let a = arr2(&[[0u8, 0, 0, 0], [0, 7, 7, 7], [0, 7, 7, 7], [0, 0, 0, 0]]);
+        let peaks = peak_local_max(a, 0, None, false, Plateau::Centre);
+        assert_eq!(rows(&peaks), vec![vec![1, 2]]);
+    }
+}
@@ -0,0 +1,167 @@
+use ndarray::{Array, Dimension, RemoveAxis, Zip};
+
+use crate::array_shape_traits::WinSh;
+use crate::border_handling::BorderMode;
+use crate::filters::{correlate_separable, gaussian_kernel_1d};
+use crate::integer_conversion_traits::WinNum;
+
+/// weighting of the local statistics used by [ssim_map]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SsimWindow {
+    /// gaussian weights with the same `sigma` on every axis and a radius of `truncate * sigma`,
+    /// see [gaussian_kernel_1d]
+    Gaussian { sigma: f64, truncate: f64 },
+    /// equal weights over a window of this length on every axis
+    Uniform(usize),
+}
+
+impl SsimWindow {
+    fn kernel(&self) -> Vec<f64> {
+        match *self {
+            SsimWindow::Gaussian { sigma, truncate } => gaussian_kernel_1d(sigma, truncate),
+            SsimWindow::Uniform(size) => vec![(size as f64).recip(); size],
+        }
+    }
+
+    /// length of the window along every axis
+    pub fn size(&self) -> usize {
+        self.kernel().len()
+    }
+}
+
+/// settings for [ssim_map], the default is the 11 element gaussian window with `sigma = 1.5`
+/// and the constants from Wang et al. (2004)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SsimParams {
+    pub window: SsimWindow,
+    /// luminance constant, `C1 = (k1 * data_range)^2`
+    pub k1: f64,
+    /// contrast constant, `C2 = (k2 * data_range)^2`
+    pub k2: f64,
+}
+
+impl Default for SsimParams {
+    fn default() -> Self {
+        SsimParams {
+            window: SsimWindow::Gaussian { sigma: 1.5, truncate: 3.5 },
+            k1: 0.01,
+            k2: 0.03,
+        }
+    }
+}
+
+/// structural similarity of the windows centred on every element of `a` and `b`,
+/// with the dynamic range taken from the element type ([WinNum::value_range])
+///
+/// see [ssim_map_f64]
+///
+/// ```
+/// use ndarray::Array2;
+/// let a: Array2<u8> = Array2::from_shape_fn((16, 16), |(r, c)| (r * 13 + c * 7) as u8);
+/// let s = ssim_map(a.clone(), a, &SsimParams::default(), BorderMode::Valid);
+/// assert!(s.iter().all(|&x| (x - 1.0).abs() < 1e-12));
+/// ```
+pub fn ssim_map<T, D>(a: Array<T, D>, b: Array<T, D>, params: &SsimParams, border: BorderMode<T>) -> Array<f64, D>
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let (lo, hi) = T::value_range();
+    let data_range = hi - lo;
+    ssim_map_f64(
+        a.mapv(|x| x.as_f64()),
+        b.mapv(|x| x.as_f64()),
+        data_range,
+        params,
+        border.map_constant(|k| k.as_f64()),
+    )
+}
+
+/// structural similarity of the windows centred on every element of two arrays with the same shape,
+/// for floating point data or integer data which does not use the full range of its type
+///
+/// the local means, variances and covariance are weighted by [SsimParams::window] and
+/// calculated with the separable, threaded [correlate_separable], variances are population
+/// (not sample) estimates
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1` and its mean is the
+/// same as `skimage.metrics.structural_similarity(..., use_sample_covariance=False)`
+pub fn ssim_map_f64<D>(
+    a: Array<f64, D>,
+    b: Array<f64, D>,
+    data_range: f64,
+    params: &SsimParams,
+    border: BorderMode<f64>,
+) -> Array<f64, D>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let kernels = vec![params.window.kernel(); a.ndim()];
+    let filter = |x: Array<f64, D>| correlate_separable(x, &kernels, border);
+    let c1 = (params.k1 * data_range).powi(2);
+    let c2 = (params.k2 * data_range).powi(2);
+
+    let aa = filter(&a * &a);
+    let bb = filter(&b * &b);
+    let ab = filter(&a * &b);
+    let mean_a = filter(a);
+    let mean_b = filter(b);
+    Zip::from(&mean_a)
+        .and(&mean_b)
+        .and(&aa)
+        .and(&bb)
+        .and(&ab)
+        .map_collect(|&ma, &mb, &aa, &bb, &ab| {
+            let (var_a, var_b, cov) = (aa - ma * ma, bb - mb * mb, ab - ma * mb);
+            ((2f64 * ma * mb + c1) * (2f64 * cov + c2))
+                / ((ma * ma + mb * mb + c1) * (var_a + var_b + c2))
+        })
+}
+
+/// mean of the [BorderMode::Valid] [ssim_map] of `a` and `b`, `1` for identical arrays
+pub fn mean_ssim<T, D>(a: Array<T, D>, b: Array<T, D>, params: &SsimParams) -> f64
+where
+    T: WinNum + Copy,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    ssim_map(a, b, params, BorderMode::Valid).mean().unwrap_or(f64::NAN)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array2, Ix2};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn uniform_matches_reference() {
+        let a: Array2<u8> = Array2::from_shape_fn((17, 13), not_a_hash);
+        let b: Array2<u8> = a.mapv(|x| x / 2 + 40);
+        let params = SsimParams { window: SsimWindow::Uniform(5), ..SsimParams::default() };
+        let s = ssim_map(a.clone(), b.clone(), &params, BorderMode::Valid);
+        assert_eq!(s.shape(), &[13, 9]);
+        let (c1, c2) = ((0.01f64 * 255.0).powi(2), (0.03f64 * 255.0).powi(2));
+        for ((x, y), v) in a.windows(Ix2(5, 5)).into_iter().zip(b.windows(Ix2(5, 5))).zip(s.iter()) {
+            let (x, y) = (x.mapv(|v| v as f64), y.mapv(|v| v as f64));
+            let (mx, my) = (x.mean().unwrap(), y.mean().unwrap());
+            let (vx, vy) = (x.var(0.0), y.var(0.0));
+            let cov = ((&x - mx) * (&y - my)).mean().unwrap();
+            let expected = ((2.0 * mx * my + c1) * (2.0 * cov + c2)) / ((mx * mx + my * my + c1) * (vx + vy + c2));
+            assert!((v - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn identical_and_inverted() {
+        let a: Array2<i16> = Array2::from_shape_fn((24, 24), |ix| not_a_hash(ix) as i16 * 100);
+        let params = SsimParams::default();
+        assert!((mean_ssim(a.clone(), a.clone(), &params) - 1.0).abs() < 1e-12);
+        let inverted = a.mapv(|x| 25500 - x);
+        assert!(mean_ssim(a.clone(), inverted, &params) < 0.0);
+        let s = ssim_map(a.clone(), a, &params, BorderMode::Reflect);
+        assert_eq!(s.shape(), &[24, 24]);
+        assert_eq!(params.window.size(), 11);
+    }
+}
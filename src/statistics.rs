@@ -0,0 +1,152 @@
+use ndarray::{Array, Dimension, RemoveAxis};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{pad_for_window, BorderMode};
+use crate::integer_conversion_traits::{FloatConv, WinNum};
+use crate::window_functions::{
+    window_central_moment, window_excess_kurtosis, window_mad, window_nan_reduce, window_skewness,
+    window_trimmed_mean, window_winsorised_mean, NanPolicy, NanReduction, MAD_NORMAL_SCALE,
+};
+
+/// higher order moment of a window, calculated by [moment_map]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Moment {
+    /// `k`-th central moment, see [window_central_moment]
+    Central(u32),
+    /// see [window_skewness]
+    Skewness,
+    /// see [window_excess_kurtosis]
+    ExcessKurtosis,
+}
+
+/// moment of the window centred on every element as [f64]
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1`
+pub fn moment_map<T, D>(arr: Array<T, D>, win_size: D, moment: Moment, border: BorderMode<T>) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    match moment {
+        Moment::Central(k) => thread_over_any_window_map(arr, win_size, move |w| window_central_moment(w, k)),
+        Moment::Skewness => thread_over_any_window_map(arr, win_size, window_skewness),
+        Moment::ExcessKurtosis => thread_over_any_window_map(arr, win_size, window_excess_kurtosis),
+    }
+}
+
+/// robust spread or location of a window, calculated by [robust_map]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RobustStatistic {
+    /// median absolute deviation, `scaled` multiplies by [MAD_NORMAL_SCALE], see [window_mad]
+    Mad { scaled: bool },
+    /// mean without the `alpha` proportion at each end, see [window_trimmed_mean]
+    TrimmedMean { alpha: f64 },
+    /// mean with the `alpha` proportion at each end clamped, see [window_winsorised_mean]
+    WinsorisedMean { alpha: f64 },
+}
+
+/// robust statistic of the window centred on every element as [f64]
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1`
+pub fn robust_map<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    statistic: RobustStatistic,
+    border: BorderMode<T>,
+) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    match statistic {
+        RobustStatistic::Mad { scaled } => {
+            let scale = if scaled { MAD_NORMAL_SCALE } else { 1f64 };
+            thread_over_any_window_map(arr, win_size, move |w| window_mad(w, scale))
+        }
+        RobustStatistic::TrimmedMean { alpha } => {
+            thread_over_any_window_map(arr, win_size, move |w| window_trimmed_mean(w, alpha))
+        }
+        RobustStatistic::WinsorisedMean { alpha } => {
+            thread_over_any_window_map(arr, win_size, move |w| window_winsorised_mean(w, alpha))
+        }
+    }
+}
+
+/// NaN aware `reduction` of the window centred on every element of a float array,
+/// NaN is propagated or skipped depending on `policy`, see [window_nan_reduce]
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1`, `BorderMode::Constant(NaN)` with
+/// [NanPolicy::Omit] reduces over the part of the window inside the array
+pub fn nan_reduce_map<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    reduction: NanReduction,
+    policy: NanPolicy,
+    border: BorderMode<T>,
+) -> Array<T, D>
+where
+    T: FloatConv + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let arr = pad_for_window(arr, &win_size, border);
+    thread_over_any_window_map(arr, win_size, move |w| T::from_f64(window_nan_reduce(w, reduction, policy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array2, Ix2};
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn maps_match_window_functions() {
+        let a: Array2<u16> = Array2::from_shape_fn((30, 21), |ix| not_a_hash(ix) as u16 * 4);
+        let win = Ix2(5, 4);
+        let variance = moment_map(a.clone(), win, Moment::Central(2), BorderMode::Valid);
+        let skew = moment_map(a.clone(), win, Moment::Skewness, BorderMode::Valid);
+        let kurt = moment_map(a.clone(), win, Moment::ExcessKurtosis, BorderMode::Valid);
+        for (((v, s), k), w) in variance.iter().zip(skew.iter()).zip(kurt.iter()).zip(a.windows(win)) {
+            assert!((v - w.mapv(|x| x as f64).var(0.0)).abs() < 1e-6);
+            assert_eq!(*s, window_skewness(w));
+            assert_eq!(*k, window_excess_kurtosis(w));
+        }
+    }
+
+    #[test]
+    fn trimmed_mean_removes_outlier() {
+        let mut a: Array2<u16> = Array2::from_elem((9, 9), 40);
+        a[[4, 4]] = 1000;
+        let trimmed = robust_map(a.clone(), Ix2(3, 3), RobustStatistic::TrimmedMean { alpha: 0.2 }, BorderMode::Valid);
+        assert!(trimmed.iter().all(|&x| x == 40.0));
+        let mad = robust_map(a, Ix2(3, 3), RobustStatistic::Mad { scaled: true }, BorderMode::Nearest);
+        assert!(mad.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn nan_omitted_at_borders() {
+        let mut a: Array2<f32> = Array2::from_shape_fn((6, 8), |(r, c)| (r * 8 + c) as f32);
+        a[[2, 3]] = f32::NAN;
+        let win = Ix2(3, 3);
+        let omit = NanPolicy::Omit { min_count: 4 };
+        let max = nan_reduce_map(a.clone(), win, NanReduction::Max, omit, BorderMode::Constant(f32::NAN));
+        assert_eq!(max.shape(), &[6, 8]);
+        assert_eq!(max[[0, 0]], 9.0);
+        assert_eq!(max[[1, 2]], 18.0);
+        let propagated = nan_reduce_map(a, win, NanReduction::Mean, NanPolicy::Propagate, BorderMode::Valid);
+        assert!(propagated[[0, 1]].is_nan() && propagated[[1, 2]].is_nan());
+        assert_eq!(propagated[[0, 0]], 9.0);
+    }
+
+    #[test]
+    fn border_keeps_shape() {
+        let a: Array2<u16> = Array2::from_shape_fn((7, 9), |ix| not_a_hash(ix) as u16 * 4);
+        let skew = moment_map(a, Ix2(3, 3), Moment::Skewness, BorderMode::Reflect);
+        assert_eq!(skew.shape(), &[7, 9]);
+        assert!(skew.iter().all(|s| s.is_finite()));
+    }
+}
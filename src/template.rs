@@ -0,0 +1,226 @@
+use std::sync::Arc;
+
+use ndarray::{Array, Array2, Axis, Dimension, IntoDimension, RemoveAxis, Slice, Zip};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::integer_conversion_traits::WinNum;
+
+/// how a template is compared with each window of an image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TemplateScore {
+    /// zero mean normalised cross correlation, between `-1` and `1`, higher is better,
+    /// `0` where the window or the template is flat
+    Ncc,
+    /// sum of squared differences, lower is better
+    Ssd,
+    /// sum of absolute differences, lower is better
+    Sad,
+}
+
+impl TemplateScore {
+    /// `true` when a higher score is a better match
+    pub fn higher_is_better(&self) -> bool {
+        matches!(self, TemplateScore::Ncc)
+    }
+}
+
+/// summed area table of `arr`, one larger than `arr` on every axis with zeros at the start
+pub fn integral_image<D: Dimension>(arr: &Array<f64, D>) -> Array<f64, D> {
+    let mut shape = arr.raw_dim();
+    for ax in 0..shape.ndim() {
+        shape[ax] += 1;
+    }
+    let mut table = Array::zeros(shape);
+    table.slice_each_axis_mut(|_| Slice::from(1..)).assign(arr);
+    for ax in 0..table.ndim() {
+        table.accumulate_axis_inplace(Axis(ax), |&prev, cur| *cur += prev);
+    }
+    table
+}
+
+/// sum of every window of `arr` from its [integral_image], the same shape as
+/// [crate::array_threading::apply_over_any_window] gives
+pub fn window_sums<D: Dimension + WinSh>(arr: &Array<f64, D>, win_size: &D) -> Array<f64, D> {
+    let table = integral_image(arr);
+    let out_shape = arr.raw_dim().size_sub_shape(win_size);
+    let ndim = out_shape.ndim();
+    let mut out = Array::zeros(out_shape.clone());
+    // inclusion-exclusion over the 2^ndim corners of the window
+    for corner in 0..1usize << ndim {
+        let view = table.slice_each_axis(|ad| {
+            let ax = ad.axis.index();
+            let start = (corner >> ax & 1) * win_size[ax];
+            Slice::from(start..start + out_shape[ax])
+        });
+        let sign = match (ndim - corner.count_ones() as usize) % 2 {
+            0 => 1f64,
+            _ => -1f64,
+        };
+        out.scaled_add(sign, &view);
+    }
+    out
+}
+
+/// score of `template` against every window of `image` with the same shape as the template,
+/// the output has the "valid" shape `image - template + 1`
+///
+/// the window sums and sums of squares needed by [TemplateScore::Ncc] and [TemplateScore::Ssd]
+/// come from integral images, the products with the template are threaded with
+/// [thread_over_any_window_map]
+///
+/// ```
+/// use ndarray::Array2;
+/// let image: Array2<u8> = Array2::from_shape_fn((6, 6), |(r, c)| (r * 7 + c * 3) as u8 % 11);
+/// let template = image.slice(ndarray::s![2..4, 1..4]).to_owned();
+/// let scores = match_template(image, template, TemplateScore::Ssd);
+/// assert_eq!(scores[[2, 1]], 0.0);
+/// ```
+pub fn match_template<T, D>(image: Array<T, D>, template: Array<T, D>, score: TemplateScore) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let win_size = template.raw_dim();
+    let n = template.len() as f64;
+    let template = template.mapv(|x| x.as_f64());
+    let image = image.mapv(|x| x.as_f64());
+    match score {
+        TemplateScore::Sad => {
+            let template = Arc::new(template);
+            thread_over_any_window_map(image, win_size, move |w| {
+                Zip::from(&w).and(template.as_ref()).fold(0f64, |a, &x, &t| a + (x - t).abs())
+            })
+        }
+        TemplateScore::Ssd => {
+            let sum_sq = window_sums(&image.mapv(|x| x * x), &win_size);
+            let template_sq = template.iter().map(|t| t * t).sum::<f64>();
+            let template = Arc::new(template);
+            let cross = thread_over_any_window_map(image, win_size, move |w| {
+                Zip::from(&w).and(template.as_ref()).fold(0f64, |a, &x, &t| a + x * t)
+            });
+            Zip::from(&sum_sq)
+                .and(&cross)
+                .map_collect(|&s, &c| (s - 2f64 * c + template_sq).max(0f64))
+        }
+        TemplateScore::Ncc => {
+            let sums = window_sums(&image, &win_size);
+            let sum_sq = window_sums(&image.mapv(|x| x * x), &win_size);
+            let template_mean = template.sum() / n;
+            let centred = template.mapv(|t| t - template_mean);
+            let template_ss = centred.iter().map(|t| t * t).sum::<f64>();
+            let centred = Arc::new(centred);
+            // the template is zero mean, so the window mean drops out of the cross term
+            let cross = thread_over_any_window_map(image, win_size, move |w| {
+                Zip::from(&w).and(centred.as_ref()).fold(0f64, |a, &x, &t| a + x * t)
+            });
+            Zip::from(&cross).and(&sums).and(&sum_sq).map_collect(|&c, &s, &sq| {
+                // differences of integral images leave rounding error behind on flat windows
+                let window_ss = sq - s * s / n;
+                match window_ss > 1e-12 * sq && template_ss > 0f64 {
+                    true => (c / (window_ss * template_ss).sqrt()).clamp(-1f64, 1f64),
+                    false => 0f64,
+                }
+            })
+        }
+    }
+}
+
+/// coordinates of the `count` best scores from [match_template], best first,
+/// ties go to the first position in logical order
+///
+/// scores within `min_distance` (largest distance along any axis) of a better match are skipped,
+/// `0` keeps every position
+pub fn best_matches<D: Dimension>(
+    scores: &Array<f64, D>,
+    score: TemplateScore,
+    count: usize,
+    min_distance: usize,
+) -> Array2<usize> {
+    let mut ranked: Vec<(D, f64)> = scores
+        .indexed_iter()
+        .filter(|(_, s)| !s.is_nan())
+        .map(|(ix, &s)| (ix.into_dimension(), s))
+        .collect();
+    // stable, so equal scores stay in logical order
+    match score.higher_is_better() {
+        true => ranked.sort_by(|a, b| b.1.total_cmp(&a.1)),
+        false => ranked.sort_by(|a, b| a.1.total_cmp(&b.1)),
+    }
+    let mut kept: Vec<D> = vec![];
+    for (ix, _) in ranked {
+        if kept.len() == count {
+            break;
+        }
+        let far_enough = kept.iter().all(|k| {
+            let distance = k
+                .slice()
+                .iter()
+                .zip(ix.slice())
+                .fold(0, |m, (&a, &b)| m.max(a.abs_diff(b)));
+            distance > min_distance
+        });
+        if far_enough {
+            kept.push(ix);
+        }
+    }
+    let ndim = scores.ndim();
+    Array2::from_shape_fn((kept.len(), ndim), |(i, ax)| kept[i][ax])
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{s, Array2, Array3, Ix2, Ix3};
+
+    use crate::array_threading::apply_over_any_window_map;
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn window_sums_match_direct() {
+        let a = Array3::from_shape_fn((7, 9, 4), |(x, y, z)| ((x * 31 + y * 7 + z * 3) % 17) as f64);
+        let win = Ix3(3, 4, 2);
+        let direct = apply_over_any_window_map(a.clone(), win, &|w| w.sum());
+        assert_eq!(window_sums(&a, &win), direct);
+    }
+
+    #[test]
+    fn scores_match_direct() {
+        let image: Array2<u8> = Array2::from_shape_fn((30, 26), not_a_hash);
+        let template = image.slice(s![11..16, 7..11]).to_owned();
+        let t = template.mapv(|x| x as f64);
+        let win = Ix2(5, 4);
+        let ncc = match_template(image.clone(), template.clone(), TemplateScore::Ncc);
+        let ssd = match_template(image.clone(), template.clone(), TemplateScore::Ssd);
+        let sad = match_template(image.clone(), template, TemplateScore::Sad);
+        let tm = &t - t.mean().unwrap();
+        for (i, w) in image.windows(win).into_iter().enumerate() {
+            let ix = [i / 23, i % 23];
+            let w = w.mapv(|x| x as f64);
+            assert!((ssd[ix] - (&w - &t).mapv(|d| d * d).sum()).abs() < 1e-6);
+            assert_eq!(sad[ix], (&w - &t).mapv(f64::abs).sum());
+            let wm = &w - w.mean().unwrap();
+            let denominator = ((&wm * &wm).sum() * (&tm * &tm).sum()).sqrt();
+            let expected = if denominator == 0.0 { 0.0 } else { (&wm * &tm).sum() / denominator };
+            assert!((ncc[ix] - expected).abs() < 1e-9);
+        }
+        assert!((ncc[[11, 7]] - 1.0).abs() < 1e-12);
+        assert_eq!(best_matches(&ncc, TemplateScore::Ncc, 1, 0), ndarray::arr2(&[[11, 7]]));
+        assert_eq!(best_matches(&ssd, TemplateScore::Ssd, 1, 0), ndarray::arr2(&[[11, 7]]));
+    }
+
+    #[test]
+    fn best_matches_spacing() {
+        let scores = ndarray::arr2(&[[0.9, 0.95, 0.1], [0.2, 0.3, 0.8], [0.95, 0.0, 0.0]]);
+        assert_eq!(
+            best_matches(&scores, TemplateScore::Ncc, 3, 0),
+            ndarray::arr2(&[[0, 1], [2, 0], [0, 0]])
+        );
+        assert_eq!(
+            best_matches(&scores, TemplateScore::Ncc, 3, 1),
+            ndarray::arr2(&[[0, 1], [2, 0], [2, 2]])
+        );
+    }
+}
@@ -0,0 +1,542 @@
+use std::f64::consts::PI;
+
+use ndarray::{Array, ArrayView, Axis, Dimension, RemoveAxis, Slice};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{centred_padding, pad_array, pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+
+/// how the raw local binary pattern bits are turned into a label
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LbpMethod {
+    /// the raw pattern, `2^points` labels
+    Default,
+    /// the smallest value of every circular bit rotation of the pattern, `2^points` labels (sparse)
+    RotationInvariant,
+    /// rotation invariant uniform patterns (Ojala `riu2`): uniform patterns (at most two
+    /// 0/1 transitions) are labelled by their number of set bits, everything else is
+    /// `points + 1`, `points + 2` labels
+    Uniform,
+    /// every uniform pattern gets its own label, everything else shares the last one,
+    /// `points * (points - 1) + 3` labels
+    NonRotationInvariantUniform,
+}
+
+impl LbpMethod {
+    /// number of histogram bins needed to hold every label for `points` neighbours
+    pub fn bins(&self, points: usize) -> usize {
+        match self {
+            LbpMethod::Default | LbpMethod::RotationInvariant => 1 << points,
+            LbpMethod::Uniform => points + 2,
+            LbpMethod::NonRotationInvariantUniform => points * (points - 1) + 3,
+        }
+    }
+
+    /// label for the raw `pattern` of `points` bits, bit `p` is neighbour `p`
+    pub fn label(&self, pattern: u32, points: usize) -> u32 {
+        let mask = match points {
+            32 => u32::MAX,
+            _ => (1u32 << points) - 1,
+        };
+        let rotate = |v: u32, r: usize| ((v >> r) | (v << ((points - r) % points))) & mask;
+        let ones = pattern.count_ones();
+        // number of 0/1 changes going round the circle
+        let transitions = (pattern ^ rotate(pattern, 1)).count_ones();
+        match self {
+            LbpMethod::Default => pattern,
+            LbpMethod::RotationInvariant => (0..points).map(|r| rotate(pattern, r)).min().unwrap(),
+            LbpMethod::Uniform => match transitions <= 2 {
+                true => ones,
+                false => points as u32 + 1,
+            },
+            LbpMethod::NonRotationInvariantUniform => {
+                let p = points as u32;
+                if transitions > 2 {
+                    p * (p - 1) + 2
+                } else if ones == 0 {
+                    0
+                } else if ones == p {
+                    p * (p - 1) + 1
+                } else {
+                    // the rotation which puts the run of ones at bit 0
+                    let start = (0..points)
+                        .find(|&r| rotate(pattern, r) == (1u32 << ones) - 1)
+                        .unwrap() as u32;
+                    1 + (ones - 1) * p + start
+                }
+            }
+        }
+    }
+}
+
+/// index into a window using only the first two axes, every other axis is at 0
+#[inline]
+fn plane_index<D: Dimension>(w: &ArrayView<f64, D>, row: usize, col: usize) -> D {
+    let mut ix = w.raw_dim();
+    for ax in 0..ix.ndim() {
+        ix[ax] = 0;
+    }
+    ix[0] = row;
+    ix[1] = col;
+    ix
+}
+
+/// snap values within rounding error of an integer, so `cos(pi / 2)` is treated as exactly zero
+#[inline]
+fn snap(x: f64) -> f64 {
+    match (x - x.round()).abs() < 1e-9 {
+        true => x.round(),
+        false => x,
+    }
+}
+
+/// neighbour offsets from the centre as `(row, col)`, neighbour 0 is to the right and
+/// they go anticlockwise
+pub fn circle_offsets(points: usize, radius: f64) -> Vec<(f64, f64)> {
+    (0..points)
+        .map(|p| {
+            let angle = 2f64 * PI * p as f64 / points as f64;
+            (snap(-radius * angle.sin()), snap(radius * angle.cos()))
+        })
+        .collect()
+}
+
+/// basic 3x3 local binary pattern of a window, the window must be at least 3x3 over the
+/// first two axes and the centre is `[1, 1]`
+///
+/// neighbours are thresholded against the centre (`neighbour >= centre` sets the bit),
+/// bit 0 is the top left neighbour and the bits go clockwise
+#[inline]
+pub fn func_lbp_3x3<T, D>(w: ArrayView<T, D>) -> u8
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    const ORDER: [(usize, usize); 8] = [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0), (1, 0)];
+    let w = w.mapv(|x| x.as_f64());
+    let w = w.view();
+    let centre = w[plane_index(&w, 1, 1)];
+    ORDER.iter().enumerate().fold(0u8, |code, (bit, &(r, c))| {
+        match w[plane_index(&w, r, c)] >= centre {
+            true => code | (1 << bit),
+            false => code,
+        }
+    })
+}
+
+/// circular local binary pattern with `points` neighbours at `radius` from the centre,
+/// neighbours which don't land on an element are bilinearly interpolated
+///
+/// the window must be `2 * ceil(radius) + 1` along the first two axes, see [lbp_window_shape]
+pub fn lbp_circular_window<T, D>(
+    w: ArrayView<T, D>,
+    offsets: &[(f64, f64)],
+    method: LbpMethod,
+) -> u32
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let w = w.mapv(|x| x.as_f64());
+    let w = w.view();
+    let c = (w.shape()[0] / 2) as f64;
+    let centre = w[plane_index(&w, c as usize, c as usize)];
+    let pattern = offsets.iter().enumerate().fold(0u32, |code, (bit, &(dr, dc))| {
+        let (r, col) = (c + dr, c + dc);
+        let (r0, c0) = (r.floor(), col.floor());
+        let (fr, fc) = (r - r0, col - c0);
+        let (r0, c0) = (r0 as usize, c0 as usize);
+        let (r1, c1) = ((r0 + 1).min(w.shape()[0] - 1), (c0 + 1).min(w.shape()[1] - 1));
+        let value = w[plane_index(&w, r0, c0)] * (1f64 - fr) * (1f64 - fc)
+            + w[plane_index(&w, r0, c1)] * (1f64 - fr) * fc
+            + w[plane_index(&w, r1, c0)] * fr * (1f64 - fc)
+            + w[plane_index(&w, r1, c1)] * fr * fc;
+        match value >= centre {
+            true => code | (1 << bit),
+            false => code,
+        }
+    });
+    method.label(pattern, offsets.len())
+}
+
+/// window shape for a circular pattern of `radius`,
+/// `2 * ceil(radius) + 1` on the first two axes and `1` on the rest
+pub fn lbp_window_shape<D: Dimension + WinSh>(radius: f64) -> D {
+    let side = 2 * radius.ceil() as usize + 1;
+    D::from_slice(&[side, side])
+}
+
+/// local binary pattern labels for every element, over the first two axes of each 2d slice
+///
+/// - `points` must be between 1 and 32
+/// - with [BorderMode::Valid] the first two axes shrink by `2 * ceil(radius)`
+///
+/// ```
+/// use ndarray::Array2;
+/// let a: Array2<u8> = Array2::from_elem((10, 10), 3);
+/// let codes = local_binary_pattern(a, 8, 1.0, LbpMethod::Uniform, BorderMode::Reflect);
+/// // flat regions are all ones, uniform with 8 set bits
+/// assert!(codes.iter().all(|&x| x == 8));
+/// ```
+pub fn local_binary_pattern<T, D>(
+    arr: Array<T, D>,
+    points: usize,
+    radius: f64,
+    method: LbpMethod,
+    border: BorderMode<T>,
+) -> Array<u32, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    assert!((1..=32).contains(&points), "points must be between 1 and 32");
+    assert!(arr.ndim() >= 2, "local binary patterns need at least 2 dimensions");
+    let win_size: D = lbp_window_shape(radius);
+    let arr = match border {
+        BorderMode::Valid => arr,
+        _ => {
+            let (before, after) = centred_padding(&win_size);
+            pad_array(&arr, &before, &after, &border)
+        }
+    };
+    let offsets = circle_offsets(points, radius);
+    thread_over_any_window_map(arr, win_size, move |w| lbp_circular_window(w, &offsets, method))
+}
+
+/// histogram of the labels in each window, the counts are placed on a new last axis of
+/// length `bins`, labels `>= bins` are ignored
+///
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let labels: Array2<u32> = Array2::from_elem((6, 6), 2);
+/// let hist = lbp_histogram(labels, Ix2(3, 3), 4);
+/// assert_eq!(hist.shape(), &[4, 4, 4]);
+/// assert_eq!(hist[[0, 0, 2]], 9);
+/// ```
+pub fn lbp_histogram<D>(labels: Array<u32, D>, win_size: D, bins: usize) -> Array<u32, D::Larger>
+where
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let counts = thread_over_any_window_map(labels, win_size, move |w: ArrayView<u32, D>| {
+        let mut hist = vec![0u32; bins];
+        for &x in w.iter() {
+            if let Some(h) = hist.get_mut(x as usize) {
+                *h += 1;
+            }
+        }
+        hist
+    });
+    let last = counts.ndim();
+    let mut shape = counts.raw_dim().insert_axis(Axis(last));
+    shape[last] = bins;
+    let flat: Vec<u32> = counts.into_iter().flatten().collect();
+    Array::from_shape_vec(shape, flat).unwrap()
+}
+
+/// Haralick texture feature of a normalised, symmetric grey level co-occurrence matrix `P`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlcmFeature {
+    /// `sum(P[i, j] * (i - j)^2)`
+    Contrast,
+    /// `sum(P[i, j] / (1 + (i - j)^2))`
+    Homogeneity,
+    /// `sqrt(sum(P[i, j]^2))`
+    Energy,
+    /// `sum(P[i, j] * (i - mean) * (j - mean)) / variance`, `1` when the window is flat
+    Correlation,
+    /// `-sum(P[i, j] * ln(P[i, j]))`
+    Entropy,
+}
+
+/// quantise `arr` linearly into `0..levels` between its smallest and largest value
+///
+/// integer values each take `1 / (hi - lo + 1)` of the range so every level covers the same
+/// number of values, float values are scaled by `levels / (hi - lo)` and the largest is put in
+/// the top level
+pub fn quantise<T, D>(arr: &Array<T, D>, levels: usize) -> Array<u16, D>
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let (lo, hi) = arr
+        .iter()
+        .fold((T::MAX, T::MIN), |(lo, hi), &x| {
+            (if x < lo { x } else { lo }, if x > hi { x } else { hi })
+        });
+    let (lo, span) = (lo.as_f64(), hi.as_f64() - lo.as_f64() + T::INTEGER as u8 as f64);
+    if span <= 0f64 {
+        return Array::zeros(arr.raw_dim());
+    }
+    let scale = levels as f64 / span;
+    arr.mapv(|x| (((x.as_f64() - lo) * scale) as usize).min(levels - 1) as u16)
+}
+
+/// sparse co-occurrence counts `(i, j, count)` of the quantised window for every offset,
+/// both `(i, j)` and `(j, i)` are counted so the matrix is symmetric
+fn window_glcm<D: Dimension>(w: ArrayView<u16, D>, offsets: &[Vec<isize>]) -> Vec<(u16, u16, u32)> {
+    let mut pairs: Vec<(u16, u16)> = vec![];
+    for offset in offsets {
+        // the elements which still have their neighbour inside the window when shifted by `offset`
+        let origin = w.slice_each_axis(|ad| {
+            let (len, o) = (ad.len as isize, offset[ad.axis.index()]);
+            Slice::from((-o).max(0)..(len - o.max(0)).max((-o).max(0)))
+        });
+        let shifted = w.slice_each_axis(|ad| {
+            let (len, o) = (ad.len as isize, offset[ad.axis.index()]);
+            Slice::from(o.max(0)..(len + o.min(0)).max(o.max(0)))
+        });
+        for (&i, &j) in origin.iter().zip(shifted.iter()) {
+            pairs.push((i, j));
+            pairs.push((j, i));
+        }
+    }
+    pairs.sort_unstable();
+    pairs
+        .chunk_by(|a, b| a == b)
+        .map(|run| (run[0].0, run[0].1, run.len() as u32))
+        .collect()
+}
+
+/// the requested features of a sparse co-occurrence matrix, all `0` when there are no pairs
+fn glcm_features_of(glcm: &[(u16, u16, u32)], features: &[GlcmFeature]) -> Vec<f64> {
+    let total = glcm.iter().map(|&(_, _, c)| c as f64).sum::<f64>();
+    if total == 0f64 {
+        return vec![0f64; features.len()];
+    }
+    let p = |c: u32| c as f64 / total;
+    // the matrix is symmetric so the row and column statistics are the same
+    let mean = glcm.iter().map(|&(i, _, c)| i as f64 * p(c)).sum::<f64>();
+    let variance = glcm.iter().map(|&(i, _, c)| (i as f64 - mean).powi(2) * p(c)).sum::<f64>();
+    features
+        .iter()
+        .map(|feature| match feature {
+            GlcmFeature::Contrast => glcm
+                .iter()
+                .map(|&(i, j, c)| (i as f64 - j as f64).powi(2) * p(c))
+                .sum(),
+            GlcmFeature::Homogeneity => glcm
+                .iter()
+                .map(|&(i, j, c)| p(c) / (1f64 + (i as f64 - j as f64).powi(2)))
+                .sum(),
+            GlcmFeature::Energy => glcm.iter().map(|&(_, _, c)| p(c).powi(2)).sum::<f64>().sqrt(),
+            GlcmFeature::Correlation => match variance < 1e-15 {
+                true => 1f64,
+                false => {
+                    glcm.iter()
+                        .map(|&(i, j, c)| (i as f64 - mean) * (j as f64 - mean) * p(c))
+                        .sum::<f64>()
+                        / variance
+                }
+            },
+            GlcmFeature::Entropy => glcm.iter().map(|&(_, _, c)| -p(c) * p(c).ln()).sum(),
+        })
+        .collect()
+}
+
+/// Haralick features from the grey level co-occurrence matrix of the window centred on every
+/// element, one channel per entry of `features` on a new last axis
+///
+/// - the array is padded according to `border` then quantised into `levels` grey levels
+///   with [quantise]
+/// - every `offsets` entry has one signed step per axis, the pairs of all offsets go into one
+///   symmetric matrix, pass a single offset to get the features of one direction
+///
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let a: Array2<u8> = Array2::from_shape_fn((8, 8), |(r, c)| ((r + c) % 2 * 255) as u8);
+/// let offsets = vec![vec![0, 1]];
+/// let f = glcm_features(a, Ix2(3, 3), &offsets, 8, &[GlcmFeature::Contrast], BorderMode::Valid);
+/// assert_eq!(f.shape(), &[6, 6, 1]);
+/// assert_eq!(f[[0, 0, 0]], 49.0);
+/// ```
+pub fn glcm_features<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    offsets: &[Vec<isize>],
+    levels: usize,
+    features: &[GlcmFeature],
+    border: BorderMode<T>,
+) -> Array<f64, D::Larger>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let quantised = quantise(&pad_for_window(arr, &win_size, border), levels);
+    let offsets = offsets.to_vec();
+    let channels = features.len();
+    let thread_features = features.to_vec();
+    let per_window = thread_over_any_window_map(quantised, win_size, move |w| {
+        glcm_features_of(&window_glcm(w, &offsets), &thread_features)
+    });
+    let last = per_window.ndim();
+    let mut shape = per_window.raw_dim().insert_axis(Axis(last));
+    shape[last] = channels;
+    let flat: Vec<f64> = per_window.into_iter().flatten().collect();
+    Array::from_shape_vec(shape, flat).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, Array2, Array3, Ix2};
+
+    use crate::array_threading::apply_over_any_window_map;
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn basic_3x3() {
+        let w = arr2(&[[9u8, 1, 9], [1, 5, 1], [9, 1, 9]]);
+        // corners are bits 0, 2, 4, 6
+        assert_eq!(func_lbp_3x3(w.view()), 0b0101_0101);
+    }
+
+    #[test]
+    fn circular_r1_p4_matches_plus_shape() {
+        // with 4 points at radius 1 no interpolation is needed
+        let w = arr2(&[[0u8, 7, 0], [2, 5, 6], [0, 4, 0]]);
+        let offsets = circle_offsets(4, 1.0);
+        assert_eq!(offsets, vec![(0.0, 1.0), (-1.0, 0.0), (0.0, -1.0), (1.0, 0.0)]);
+        // right 6 >= 5, up 7 >= 5, left 2 < 5, down 4 < 5
+        assert_eq!(lbp_circular_window(w.view(), &offsets, LbpMethod::Default), 0b0011);
+    }
+
+    #[test]
+    fn labels() {
+        // 0b0011_1000 rotated to the bottom is 0b0000_0111
+        assert_eq!(LbpMethod::RotationInvariant.label(0b0011_1000, 8), 0b0000_0111);
+        assert_eq!(LbpMethod::Uniform.label(0b0011_1000, 8), 3);
+        assert_eq!(LbpMethod::Uniform.label(0b0101_0000, 8), 9);
+        assert_eq!(LbpMethod::Uniform.label(0, 8), 0);
+        // every label fits in the number of bins
+        for method in [
+            LbpMethod::Default,
+            LbpMethod::RotationInvariant,
+            LbpMethod::Uniform,
+            LbpMethod::NonRotationInvariantUniform,
+        ] {
+            let bins = method.bins(8) as u32;
+            let mut seen = vec![false; bins as usize];
+            for pattern in 0..256u32 {
+                let l = method.label(pattern, 8);
+                assert!(l < bins);
+                seen[l as usize] = true;
+            }
+            if method != LbpMethod::RotationInvariant {
+                assert!(seen.iter().all(|&s| s), "{:?} leaves bins empty", method);
+            }
+        }
+    }
+
+    #[test]
+    fn rotation_invariant_under_rotation() {
+        let a: Array2<u8> = Array2::from_shape_fn((30, 30), not_a_hash);
+        let rotated = a.t().slice(ndarray::s![.., ..;-1]).to_owned();
+        let la = local_binary_pattern(a, 8, 1.0, LbpMethod::Uniform, BorderMode::Valid);
+        let lr = local_binary_pattern(rotated, 8, 1.0, LbpMethod::Uniform, BorderMode::Valid);
+        // rotating the image by 90 degrees shifts the pattern by 2 bits, same riu2 label
+        assert_eq!(la.t().slice(ndarray::s![.., ..;-1]), lr);
+    }
+
+    #[test]
+    fn over_3d_slices() {
+        let a: Array3<u8> = Array3::from_shape_fn((12, 10, 3), |(x, y, c)| not_a_hash((x + c, y)));
+        let codes = local_binary_pattern(a.clone(), 8, 1.5, LbpMethod::Default, BorderMode::Valid);
+        assert_eq!(codes.shape(), &[8, 6, 3]);
+        let channel = a.index_axis(Axis(2), 1).to_owned();
+        let single = local_binary_pattern(channel, 8, 1.5, LbpMethod::Default, BorderMode::Valid);
+        assert_eq!(codes.index_axis(Axis(2), 1), single);
+    }
+
+    #[test]
+    fn histogram_counts() {
+        let labels: Array2<u32> = Array2::from_shape_fn((8, 9), |(a, b)| ((a + b) % 5) as u32);
+        let hist = lbp_histogram(labels.clone(), Ix2(3, 3), 5);
+        assert_eq!(hist.shape(), &[6, 7, 5]);
+        let direct = apply_over_any_window_map(labels, Ix2(3, 3), &|w: ArrayView<u32, Ix2>| {
+            w.iter().filter(|&&x| x == 3).count() as u32
+        });
+        assert_eq!(hist.index_axis(Axis(2), 3), direct);
+        assert!(hist.sum_axis(Axis(2)).iter().all(|&x| x == 9));
+    }
+
+    #[test]
+    fn glcm_matches_dense_matrix() {
+        let a: Array2<u8> = Array2::from_shape_fn((12, 10), not_a_hash);
+        let levels = 6;
+        let win = Ix2(4, 5);
+        let offsets = vec![vec![0, 1], vec![1, -1], vec![2, 0]];
+        let all = [
+            GlcmFeature::Contrast,
+            GlcmFeature::Homogeneity,
+            GlcmFeature::Energy,
+            GlcmFeature::Correlation,
+            GlcmFeature::Entropy,
+        ];
+        let f = glcm_features(a.clone(), win, &offsets, levels, &all, BorderMode::Valid);
+        assert_eq!(f.shape(), &[9, 6, 5]);
+
+        let q = quantise(&a, levels);
+        for ((r, c), w) in q.windows(win).into_iter().enumerate().map(|(i, w)| ((i / 6, i % 6), w)) {
+            let mut m = Array2::<f64>::zeros((levels, levels));
+            for o in offsets.iter() {
+                for ((y, x), &i) in w.indexed_iter() {
+                    let (y2, x2) = (y as isize + o[0], x as isize + o[1]);
+                    if (0..4).contains(&y2) && (0..5).contains(&x2) {
+                        let j = w[[y2 as usize, x2 as usize]];
+                        m[[i as usize, j as usize]] += 1.0;
+                        m[[j as usize, i as usize]] += 1.0;
+                    }
+                }
+            }
+            m /= m.sum();
+            let mean: f64 = m.indexed_iter().map(|((i, _), p)| i as f64 * p).sum();
+            let var: f64 = m.indexed_iter().map(|((i, _), p)| (i as f64 - mean).powi(2) * p).sum();
+            let expected = [
+                m.indexed_iter().map(|((i, j), p)| (i as f64 - j as f64).powi(2) * p).sum::<f64>(),
+                m.indexed_iter().map(|((i, j), p)| p / (1.0 + (i as f64 - j as f64).powi(2))).sum(),
+                m.iter().map(|p| p * p).sum::<f64>().sqrt(),
+                match var < 1e-15 {
+                    true => 1.0,
+                    false => {
+                        m.indexed_iter().map(|((i, j), p)| (i as f64 - mean) * (j as f64 - mean) * p).sum::<f64>() / var
+                    }
+                },
+                m.iter().filter(|&&p| p > 0.0).map(|p| -p * p.ln()).sum(),
+            ];
+            for (k, e) in expected.iter().enumerate() {
+                assert!((f[[r, c, k]] - e).abs() < 1e-9, "feature {} at {:?}", k, (r, c));
+            }
+        }
+    }
+
+    #[test]
+    fn glcm_float_levels() {
+        // floats fill every level up to the largest value, ints keep one level per value here
+        assert_eq!(quantise(&arr1(&[0.0f64, 0.5, 0.74, 1.0]), 4), arr1(&[0, 2, 2, 3]));
+        let levels = 5;
+        let q: Array2<u8> = Array2::from_shape_fn((9, 8), |ix| not_a_hash(ix) % levels as u8);
+        let top = |x: u8| if x as usize == levels - 1 { levels as f64 } else { x as f64 };
+        let a = q.mapv(|x| 0.5 + 0.25 * top(x));
+        assert_eq!(quantise(&a, levels), quantise(&q, levels));
+        let features = [GlcmFeature::Contrast, GlcmFeature::Correlation, GlcmFeature::Entropy];
+        let offsets = [vec![0, 1], vec![1, 0]];
+        assert_eq!(
+            glcm_features(a, Ix2(3, 4), &offsets, levels, &features, BorderMode::Valid),
+            glcm_features(q, Ix2(3, 4), &offsets, levels, &features, BorderMode::Valid)
+        );
+    }
+
+    #[test]
+    fn glcm_flat_window() {
+        let a: Array2<u16> = Array2::from_elem((5, 5), 300);
+        let features = [GlcmFeature::Correlation, GlcmFeature::Entropy, GlcmFeature::Energy];
+        let f = glcm_features(a, Ix2(3, 3), &[vec![1, 1]], 16, &features, BorderMode::Reflect);
+        assert_eq!(f.shape(), &[5, 5, 3]);
+        assert!(f.outer_iter().flat_map(|r| r.outer_iter().map(|c| c.to_vec()).collect::<Vec<_>>())
+            .all(|c| c == vec![1.0, 0.0, 1.0]));
+    }
+
+}
@@ -0,0 +1,203 @@
+use ndarray::{Array, Dimension, RemoveAxis, Slice, Zip};
+
+use crate::array_shape_traits::WinSh;
+use crate::array_threading::thread_over_any_window_map;
+use crate::border_handling::{centred_padding, pad_for_window, BorderMode};
+use crate::integer_conversion_traits::WinNum;
+use crate::window_functions::window_mean_population_std;
+
+/// local thresholding methods, `m` and `s` are the mean and population standard deviation
+/// of the window around each element
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdMethod {
+    /// `m + k * s`, `k` is usually negative, around `-0.2`
+    Niblack { k: f64 },
+    /// `m * (1 + k * (s / r - 1))`, `k` around `0.2` to `0.5`, `r` is the dynamic range of the
+    /// standard deviation, see [sauvola_default_r]
+    Sauvola { k: f64, r: f64 },
+    /// `(1 - k) * m + k * min + k * (s / max_s) * (m - min)`, where `min` is the smallest value
+    /// in the array and `max_s` the largest local standard deviation, `k` around `0.5`
+    Wolf { k: f64 },
+    /// `(local_min + local_max) / 2`, windows with a contrast (`local_max - local_min`) below
+    /// `contrast_threshold` fall back to the middle of the range of `T`
+    Bernsen { contrast_threshold: f64 },
+}
+
+/// half of the [WinNum::value_range] of `T`, the usual `r` for [ThresholdMethod::Sauvola]
+/// (`127.5` for [u8], `1.0` for floats)
+pub fn sauvola_default_r<T: WinNum>() -> f64 {
+    let (lo, hi) = T::value_range();
+    (hi - lo) * 0.5
+}
+
+/// local mean and population standard deviation of every window, the statistics shared by
+/// [ThresholdMethod::Niblack], [ThresholdMethod::Sauvola] and [ThresholdMethod::Wolf]
+pub fn local_mean_std<T, D>(arr: Array<T, D>, win_size: D, border: BorderMode<T>) -> Array<(f64, f64), D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    thread_over_any_window_map(pad_for_window(arr, &win_size, border), win_size, window_mean_population_std)
+}
+
+/// threshold for every element, calculated from the window centred on it
+///
+/// with [BorderMode::Valid] the output shrinks by `window - 1`,
+/// the element a threshold belongs to is at `window / 2` inside its window
+///
+/// ```
+/// use ndarray::{Array2, Ix2};
+/// let a: Array2<u8> = Array2::from_elem((9, 9), 50);
+/// let t = threshold_map(a, Ix2(3, 3), ThresholdMethod::Niblack { k: -0.2 }, BorderMode::Reflect);
+/// assert!(t.iter().all(|&x| x == 50.0));
+/// ```
+pub fn threshold_map<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    method: ThresholdMethod,
+    border: BorderMode<T>,
+) -> Array<f64, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    match method {
+        ThresholdMethod::Niblack { k } => local_mean_std(arr, win_size, border).mapv(|(m, s)| m + k * s),
+        ThresholdMethod::Sauvola { k, r } => local_mean_std(arr, win_size, border)
+            .mapv(|(m, s)| m * (1f64 + k * (s / r - 1f64))),
+        ThresholdMethod::Wolf { k } => {
+            let min = arr.iter().fold(T::MAX, |a, &x| if x < a { x } else { a }).as_f64();
+            let stats = local_mean_std(arr, win_size, border);
+            let max_s = stats.iter().fold(0f64, |a, &(_, s)| a.max(s));
+            stats.mapv(|(m, s)| {
+                let ratio = match max_s > 0f64 {
+                    true => s / max_s,
+                    false => 0f64,
+                };
+                (1f64 - k) * m + k * min + k * ratio * (m - min)
+            })
+        }
+        ThresholdMethod::Bernsen { contrast_threshold } => {
+            let (lo, hi) = T::value_range();
+            let fallback = (lo + hi) * 0.5;
+            let arr = pad_for_window(arr, &win_size, border);
+            thread_over_any_window_map(arr, win_size, move |w| {
+                let (lo, hi) = w
+                    .iter()
+                    .fold((T::MAX, T::MIN), |(lo, hi), &x| {
+                        (if x < lo { x } else { lo }, if x > hi { x } else { hi })
+                    });
+                let (lo, hi) = (lo.as_f64(), hi.as_f64());
+                match hi - lo < contrast_threshold {
+                    true => fallback,
+                    false => (lo + hi) * 0.5,
+                }
+            })
+        }
+    }
+}
+
+/// binary mask, `true` where an element is greater than its [threshold_map] value
+///
+/// with [BorderMode::Valid] the input is cropped to line up with the thresholds
+pub fn threshold_mask<T, D>(
+    arr: Array<T, D>,
+    win_size: D,
+    method: ThresholdMethod,
+    border: BorderMode<T>,
+) -> Array<bool, D>
+where
+    T: WinNum + Copy + Send + Sync + 'static,
+    D: Dimension + WinSh + RemoveAxis + Copy + 'static,
+{
+    let thresholds = threshold_map(arr.clone(), win_size, method, border);
+    // the threshold from the window starting at `i` belongs to element `i + window / 2`
+    let arr = match border {
+        BorderMode::Valid => {
+            let (before, _) = centred_padding(&win_size);
+            let shape = thresholds.raw_dim();
+            arr.slice_each_axis(|ad| {
+                let ax = ad.axis.index();
+                Slice::from(before[ax]..before[ax] + shape[ax])
+            })
+            .to_owned()
+        }
+        _ => arr,
+    };
+    Zip::from(&arr)
+        .and(&thresholds)
+        .map_collect(|&x, &t| x.as_f64() > t)
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array2, Ix2};
+
+    use crate::array_threading::apply_over_any_window;
+    use crate::window_functions::func_fast_population_std;
+
+    use crate::window_functions::not_a_hash;
+
+    use super::*;
+
+    #[test]
+    fn shares_population_std() {
+        let a: Array2<u8> = Array2::from_shape_fn((20, 15), not_a_hash);
+        let stats = local_mean_std(a.clone(), Ix2(5, 3), BorderMode::Valid);
+        let std = apply_over_any_window(a, Ix2(5, 3), func_fast_population_std);
+        assert_eq!(stats.mapv(|(_, s)| u8::from_f64(s)), std);
+    }
+
+    #[test]
+    fn formulas() {
+        let a: Array2<u8> = Array2::from_shape_fn((12, 12), not_a_hash);
+        let win = Ix2(3, 3);
+        let stats = local_mean_std(a.clone(), win, BorderMode::Reflect);
+        let niblack = threshold_map(a.clone(), win, ThresholdMethod::Niblack { k: -0.2 }, BorderMode::Reflect);
+        let sauvola = threshold_map(
+            a.clone(),
+            win,
+            ThresholdMethod::Sauvola { k: 0.5, r: sauvola_default_r::<u8>() },
+            BorderMode::Reflect,
+        );
+        for ((n, s), (m, sd)) in niblack.iter().zip(sauvola.iter()).zip(stats.iter()) {
+            assert!((n - (m - 0.2 * sd)).abs() < 1e-12);
+            assert!((s - m * (1.0 + 0.5 * (sd / 127.5 - 1.0))).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn bernsen_low_contrast_falls_back() {
+        let mut a: Array2<u8> = Array2::from_elem((7, 7), 10);
+        a[[3, 3]] = 200;
+        let t = threshold_map(
+            a.clone(),
+            Ix2(3, 3),
+            ThresholdMethod::Bernsen { contrast_threshold: 15.0 },
+            BorderMode::Nearest,
+        );
+        assert_eq!(t[[3, 3]], 105.0);
+        assert_eq!(t[[0, 0]], 127.5);
+        let mask = threshold_mask(a.clone(), Ix2(3, 3), ThresholdMethod::Bernsen { contrast_threshold: 15.0 }, BorderMode::Nearest);
+        assert_eq!(mask.iter().filter(|&&m| m).count(), 1);
+        assert!(mask[[3, 3]]);
+
+        // floats fall back to the middle of -1..1
+        let floats = a.mapv(|x| x as f32 / 255.0);
+        let t = threshold_map(floats, Ix2(3, 3), ThresholdMethod::Bernsen { contrast_threshold: 0.05 }, BorderMode::Nearest);
+        assert_eq!(t[[0, 0]], 0.0);
+        assert_eq!(sauvola_default_r::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn wolf_and_valid_mask_line_up() {
+        let a: Array2<u8> = Array2::from_shape_fn((16, 13), not_a_hash);
+        let method = ThresholdMethod::Wolf { k: 0.5 };
+        let same = threshold_mask(a.clone(), Ix2(5, 5), method, BorderMode::Reflect);
+        let valid = threshold_mask(a, Ix2(5, 5), method, BorderMode::Valid);
+        assert_eq!(valid.shape(), &[12, 9]);
+        // Wolf uses whole array statistics, so only compare the shapes and a sanity count
+        assert_eq!(same.shape(), &[16, 13]);
+        assert!(valid.iter().any(|&x| x) && valid.iter().any(|&x| !x));
+    }
+}
@@ -1,95 +1,156 @@
+use std::cmp::Ordering;
+
 use ndarray::{ArrayView, Dimension, Ix1};
+use num_traits::{CheckedAdd, CheckedMul, CheckedSub, NumCast};
 
-use crate::integer_conversion_traits::{IntConv, SignedInt};
+use crate::integer_conversion_traits::{ConversionError, ConversionPolicy, FloatConv, Overflow, Rounding, SignedInt, WinNum};
 
 pub type WinFunc<T, D> = fn(ArrayView<T, D>) -> T;
-/// helper to return a function from it's identifier, used for python implementation
-#[cfg(feature = "default")]
+/// counterpart of a built-in [WinFunc] which writes its result into the element type with a
+/// [ConversionPolicy] instead of the fixed rounding of the [WinFunc]
+pub type ConvertedWinFunc<T, D> = fn(ArrayView<T, D>, ConversionPolicy) -> Result<T, ConversionError>;
+/// built-in [WinFunc], its [ConvertedWinFunc] (`None` when the result is an element of the
+/// window and needs no conversion), whether it sums the window in [WinNum::Acc], name and description
+type FunctionInformation<T, D> = (WinFunc<T, D>, Option<ConvertedWinFunc<T, D>>, bool, &'static str, &'static str);
+/// helper to return a function from it's identifier, used for python implementation and tests
 pub fn get_func<T, D>(i: usize) -> Result<fn(ArrayView<T, D>) -> T, &'static str>
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
+    D: Dimension,
+{
+    match get_function_information(i) {
+        Ok((f, _, _, _, _)) => Ok(f),
+        Err(x) => Err(x),
+    }
+}
+/// helper to return the [ConvertedWinFunc] of a function from it's identifier,
+/// `None` for functions which return an element of the window
+pub fn get_func_converted<T, D>(i: usize) -> Result<Option<ConvertedWinFunc<T, D>>, &'static str>
+where
+    T: WinNum + Clone,
     D: Dimension,
 {
     match get_function_information(i) {
-        Ok((f, _, _)) => Ok(f),
+        Ok((_, f, _, _, _)) => Ok(f),
+        Err(x) => Err(x),
+    }
+}
+/// helper to return whether a function sums the window in [WinNum::Acc] from it's identifier,
+/// only these functions can overflow the accumulator, see [sum_as_f64]
+pub fn get_func_accumulates(i: usize) -> Result<bool, &'static str> {
+    match get_function_information::<u32, Ix1>(i) {
+        Ok((_, _, x, _, _)) => Ok(x),
         Err(x) => Err(x),
     }
 }
-#[cfg(feature = "default")]
-fn get_function_information<T, D>(
-    i: usize,
-) -> Result<(fn(ArrayView<T, D>) -> T, &'static str, &'static str), &'static str>
+fn get_function_information<T, D>(i: usize) -> Result<FunctionInformation<T, D>, &'static str>
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
     D: Dimension,
 {
     match i {
         0 => Ok((
             func_window_max,
+            None,
+            false,
             "func_window_max",
             "return the maximum value of the window",
         )),
         1 => Ok((
             func_window_min,
+            None,
+            false,
             "func_window_min",
             "return the minimum value of the window",
         )),
         2 => Ok((
             func_stdev_ddof_0,
+            Some(|w, p| p.convert(window_std(w, 0))),
+            false,
             "func_stdev_ddof_0",
-            "apply rust ndarray::std(ddof:0) over the window (uses float64 values) \
-            then round to input dtype",
+            "population standard deviation of the window calculated the same way as numpy.std(ddof=0) \
+            (uses float64 values) then round to input dtype",
         )),
         3 => Ok((
             func_stdev_ddof_1,
+            Some(|w, p| p.convert(window_std(w, 1))),
+            false,
             "func_stdev_ddof_1",
-            "apply rust ndarray::std(ddof:1) over the window (uses float64 values) \
-            then round to input dtype",
+            "sample standard deviation of the window calculated the same way as numpy.std(ddof=1) \
+            (uses float64 values) then round to input dtype",
         )),
         4 => Ok((
             func_area_contrast,
+            Some(|w, p| p.convert(window_mean_population_std(w).1)),
+            true,
             "func_area_contrast",
-            "TODO:Create Description",
+            "RMS contrast, the population standard deviation of the window (uses integer addition \
+            for the mean) rounded to input dtype",
         )),
         5 => Ok((
             func_fast_std,
+            // the abs() of func_fast_std does not change the square
+            Some(|w, p| p.convert(window_mean_population_std(w).1)),
+            true,
             "func_fast_std",
             "similar to standard deviation, trades precision for speed, uses integer addition for \
             first stage the n float 64 for second stage before returning as input dtype",
         )),
         6 => Ok((
             func_fast_std_clamp,
+            Some(|w, p| p.convert(window_mean_population_std(w).1 * 2f64)),
+            true,
             "func_fast_std_clamp",
             "run func_fast_std then double before converting back into input type",
         )),
         7 => Ok((
             func_fast_population_std,
+            Some(|w, p| p.convert(window_mean_population_std(w).1)),
+            true,
             "func_fast_population_std",
             "TODO:Create Description",
         )),
         8 => Ok((
             func_fast_sample_std,
+            Some(|w, p| p.convert(window_fast_sample_std(w))),
+            true,
             "func_fast_sample_std",
             "TODO:Create Description",
         )),
+        9 => Ok((
+            func_michelson_contrast,
+            Some(|w, p| p.convert(window_michelson_contrast(w) * T::full_scale())),
+            false,
+            "func_michelson_contrast",
+            "Michelson contrast (max - min) / (max + min) of the window, scaled so 1.0 is the \
+            maximum of the input dtype",
+        )),
+        10 => Ok((
+            func_exact_population_std,
+            Some(window_exact_std_converted),
+            true,
+            "func_exact_population_std",
+            "population standard deviation of integer windows with integer arithmetic only and an exact \
+            integer square root, rounded half up (or with the rounding of the conversion policy), \
+            the same result on every platform \
+            (float windows use func_fast_population_std)",
+        )),
         _ => Err("No Function Found for Value"),
     }
 }
 /// helper to get function name, used for python implementation
-#[cfg(feature = "default")]
 #[inline]
 pub fn get_func_name(i: usize) -> Result<&'static str, &'static str> {
     match get_function_information::<u32, Ix1>(i) {
-        Ok((_, x, _)) => Ok(x),
+        Ok((_, _, _, x, _)) => Ok(x),
         Err(x) => Err(x),
     }
 }
 /// helper to get function description, used for python implementation
-#[cfg(feature = "default")]
 #[inline]
 pub fn get_func_description(i: usize) -> Result<&'static str, &'static str> {
     match get_function_information::<u32, Ix1>(i) {
-        Ok((_, _, x)) => Ok(x),
+        Ok((_, _, _, _, x)) => Ok(x),
         Err(x) => Err(x),
     }
 }
@@ -101,74 +162,205 @@ pub fn get_func_description(i: usize) -> Result<&'static str, &'static str> {
 #[inline]
 pub fn func_window_max<T, D: Dimension>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
 {
-    w.iter().fold(T::MIN, |a: T, f: &T| a.max(f.clone()))
+    w.iter().fold(T::MIN, |a: T, f: &T| match *f > a {
+        true => f.clone(),
+        false => a,
+    })
 }
 
 #[inline]
 pub fn func_window_min<T, D: Dimension>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
+{
+    w.iter().fold(T::MAX, |a: T, f: &T| match *f < a {
+        true => f.clone(),
+        false => a,
+    })
+}
+
+/// maximum of the window and its offset in logical (row major) order,
+/// ties go to the first maximum
+#[inline]
+pub fn window_max_offset<T, D: Dimension>(w: ArrayView<T, D>) -> (T, usize)
+where
+    T: WinNum + Clone,
 {
-    w.iter().fold(T::MAX, |a: T, f: &T| a.min(f.clone()))
+    w.iter().enumerate().fold((T::MIN, 0), |(m, o), (i, x)| match *x > m {
+        true => (x.clone(), i),
+        false => (m, o),
+    })
 }
 
-/// builtin rms with ndarray
+/// minimum of the window and its offset in logical (row major) order,
+/// ties go to the first minimum
+#[inline]
+pub fn window_min_offset<T, D: Dimension>(w: ArrayView<T, D>) -> (T, usize)
+where
+    T: WinNum + Clone,
+{
+    w.iter().enumerate().fold((T::MAX, 0), |(m, o), (i, x)| match *x < m {
+        true => (x.clone(), i),
+        false => (m, o),
+    })
+}
+
+/// population standard deviation, see [window_std]
 #[inline]
 pub fn func_stdev_ddof_0<T, D: Dimension>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
 {
-    let w = w.mapv(|elem: T| elem.as_f64());
-    let w = w.std(0f64);
-    T::from_f64(w)
+    T::from_f64(window_std(w, 0))
 }
 
-/// builtin standard deviation calculations with ndarray
+/// sample standard deviation, see [window_std]
 #[inline]
 pub fn func_stdev_ddof_1<T, D: Dimension>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv + Clone,
+    T: WinNum + Clone,
+{
+    T::from_f64(window_std(w, 1))
+}
+
+/// standard deviation of the window with the same [f64] operations in the same order as
+/// `numpy.std(window, ddof=ddof)`, so the result is bit for bit the same as numpy's
+/// for integer windows
+///
+/// truncated with [ConversionPolicy::NUMPY](crate::integer_conversion_traits::ConversionPolicy::NUMPY)
+/// it reproduces `int(np.std(window))`
+#[inline]
+pub fn window_std<T, D>(w: ArrayView<T, D>, ddof: usize) -> f64
+where
+    T: WinNum,
+    D: Dimension,
 {
-    let w = w.mapv(|elem: T| elem.as_f64());
-    let w = w.std(1f64);
-    T::from_f64(w)
+    let values: Vec<f64> = w.iter().map(|x| x.as_f64()).collect();
+    let mean = numpy_pairwise_sum(&values) / values.len() as f64;
+    let squares: Vec<f64> = values.iter().map(|x| (x - mean) * (x - mean)).collect();
+    (numpy_pairwise_sum(&squares) / values.len().saturating_sub(ddof) as f64).sqrt()
+}
+
+/// the pairwise summation numpy uses for contiguous [f64] sums,
+/// 8 accumulators for blocks of up to 128 values, longer slices are split in two
+pub fn numpy_pairwise_sum(values: &[f64]) -> f64 {
+    const BLOCK: usize = 128;
+    let n = values.len();
+    if n < 8 {
+        values.iter().fold(0f64, |a, x| a + x)
+    } else if n <= BLOCK {
+        let mut r = [0f64; 8];
+        r.copy_from_slice(&values[..8]);
+        let unrolled = n - n % 8;
+        for chunk in values[8..unrolled].chunks_exact(8) {
+            for (r, x) in r.iter_mut().zip(chunk) {
+                *r += x;
+            }
+        }
+        let res = ((r[0] + r[1]) + (r[2] + r[3])) + ((r[4] + r[5]) + (r[6] + r[7]));
+        values[unrolled..].iter().fold(res, |a, x| a + x)
+    } else {
+        let half = n / 2;
+        let half = half - half % 8;
+        numpy_pairwise_sum(&values[..half]) + numpy_pairwise_sum(&values[half..])
+    }
 }
 
-/// based on [wikipedia RMS](https://wikimedia.org/api/rest_v1/media/math/render/svg/0197e4c18468102bbe81e936bca27f87e03cf7f8)
+/// RMS contrast, the population standard deviation of the window in the units of the input,
+/// based on [wikipedia RMS contrast](https://en.wikipedia.org/wiki/Contrast_(vision)#RMS_contrast)
+///
+/// the same value as [func_fast_population_std], divide by the range of `T` for the
+/// normalised contrast
 #[inline]
 pub fn func_area_contrast<T, D>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv,
+    T: WinNum,
     D: Dimension,
 {
-    let len = w.len() as f64;
-    let (mut p1, mut p2) = (0f64, T::L_ZERO);
-    for (i, x) in w.iter().enumerate() {
-        p2 = p2 + x.as_larger_int();
-        p1 = p1 + x.as_f64().powi(i as i32) - T::larger_int_as_f64(p2);
+    let (_, rms) = window_mean_population_std(w);
+    T::from_f64(rms)
+}
+
+/// Michelson contrast `(max - min) / (max + min)` scaled to `0..=T::MAX`,
+/// float windows are not scaled (see [WinNum::full_scale])
+///
+/// see [window_michelson_contrast] for the unscaled value
+#[inline]
+pub fn func_michelson_contrast<T, D>(w: ArrayView<T, D>) -> T
+where
+    T: WinNum,
+    D: Dimension,
+{
+    T::from_f64(window_michelson_contrast(w) * T::full_scale())
+}
+
+/// Michelson contrast `(max - min) / (max + min)` of the window,
+/// between `0` and `1` for integer types, `0` when `max + min` is `0`
+///
+/// signed integers are offset by `-T::MIN` first, so the smallest value of the type is black,
+/// float values are used as they are and can give contrasts above `1` or below `0` when the
+/// window has negative values
+#[inline]
+pub fn window_michelson_contrast<T, D>(w: ArrayView<T, D>) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let (lo, hi) = w.iter().fold((T::MAX, T::MIN), |(lo, hi), x| {
+        (
+            if *x < lo { x.clone() } else { lo },
+            if *x > hi { x.clone() } else { hi },
+        )
+    });
+    let offset = match T::INTEGER {
+        true => T::MIN.as_f64(),
+        false => 0f64,
+    };
+    let (lo, hi) = (lo.as_f64() - offset, hi.as_f64() - offset);
+    match hi + lo == 0f64 {
+        true => 0f64,
+        false => (hi - lo) / (hi + lo),
     }
+}
 
-    let std = p1.sqrt() / len;
-    T::from_f64(std)
+/// Weber contrast `(centre - background) / background`, `centre` is the element at
+/// `window / 2` and `background` the mean of the rest of the window,
+/// `0` when the background is `0`
+#[inline]
+pub fn window_weber_contrast<T, D>(w: ArrayView<T, D>) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    if w.len() < 2 {
+        return 0f64;
+    }
+    let mut centre_ix = w.raw_dim();
+    for ax in 0..centre_ix.ndim() {
+        centre_ix[ax] /= 2;
+    }
+    let centre = w[centre_ix].clone();
+    let total = sum_as_f64(w.iter());
+    let background = (total - centre.as_f64()) / (w.len() - 1) as f64;
+    match background == 0f64 {
+        true => 0f64,
+        false => (centre.as_f64() - background) / background,
+    }
 }
 
 /// faster but less precise than the builtin standard deviation calculation,
-/// uses unsigned integer addition
-/// overflows are possible on u64 arrays, or u32 arrays with more than `4*10^6` items
+/// uses integer addition for the mean, see [sum_as_f64]
 #[deprecated(note="use func_fast_population_std()")]
 #[inline]
 pub fn func_fast_std<T, D>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv,
+    T: WinNum,
     D: Dimension,
 {
     let len_inv = (w.len() as f64).recip();
-    let mean: f64 = T::larger_int_as_f64(
-        w.iter()
-            .fold(T::L_ZERO, |a: T::LargerInt, x: &T| a + x.as_larger_int()),
-    ) * len_inv;
+    let mean: f64 = sum_as_f64(w.iter()) * len_inv;
     let flt: f64 = w
         .iter()
         .fold(0f64, |a: f64, x: &T| a + (x.as_f64() - mean).abs().powi(2));
@@ -176,18 +368,37 @@ where
     T::from_f64((flt * len_inv).sqrt())
 }
 
+/// population standard deviation with a truncated integer mean and an integer sum of squares,
+/// both in [SignedInt::LargerS]
+///
+/// when a sum would overflow (only possible with 64 and 128 bit types) the deviations are
+/// summed in [f64] instead
 #[inline]
 pub fn func_fast_std_pure_int<T, D>(w: ArrayView<T, D>) -> T
 where
     T: SignedInt,
     D: Dimension,
 {
-    let mean: T::LargerS = w.iter()
-        .fold(T::L_ZERO, |a:T::LargerS, x:&T| a + x.to_larger()) / T::usize_to_larger(w.len());
-    let flt = w.iter()
-        .fold(T::L_ZERO, |a:T::LargerS, x:&T| a + T::larger_pow2(x.to_larger()-mean));
+    let exact = w
+        .iter()
+        .try_fold(T::L_ZERO, |a: T::LargerS, x: &T| a.checked_add(&x.to_larger()))
+        .and_then(|total| {
+            let mean = total / T::usize_to_larger(w.len());
+            w.iter().try_fold(T::L_ZERO, |a: T::LargerS, x: &T| {
+                let d = x.to_larger().checked_sub(&mean)?;
+                a.checked_add(&d.checked_mul(&d)?)
+            })
+        });
+    let flt = match exact {
+        Some(flt) => T::larger_to_f64(flt),
+        None => {
+            let total = w.iter().fold(0f64, |a, x| a + T::larger_to_f64(x.to_larger()));
+            let mean = (total / w.len() as f64).trunc();
+            w.iter().fold(0f64, |a, x| a + (T::larger_to_f64(x.to_larger()) - mean).powi(2))
+        }
+    };
     T::from_f64(
-        (T::larger_to_f64(flt) / (w.len() as f64)).sqrt()
+        (flt / (w.len() as f64)).sqrt()
     )
 }
 
@@ -200,24 +411,529 @@ where
 /// let nd_std = array.std(0);
 /// assert_eq![pop_std, nd_std] // this is not always correct 2023-01-13
 /// ```
-/// uses unsigned integer addition
-/// overflows are possible on u64 arrays, or u32 arrays with more than `4*10^6` items
+/// uses integer addition for the mean, see [sum_as_f64]
 #[inline]
 pub fn func_fast_population_std<T, D>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv,
+    T: WinNum,
+    D: Dimension,
+{
+    let (_, std) = window_mean_population_std(w);
+    T::from_f64(std)
+}
+
+/// population standard deviation of integer windows calculated with [window_exact_std],
+/// always rounded half up and saturated to `T` ([ConversionPolicy::DEFAULT]),
+/// see [window_exact_std_converted] for other policies
+///
+/// float windows, and [i128] windows spread too wide for [window_exact_std], use
+/// [func_fast_population_std]
+#[inline]
+pub fn func_exact_population_std<T, D>(w: ArrayView<T, D>) -> T
+where
+    T: WinNum,
+    D: Dimension,
+{
+    match window_exact_std(w.view(), Rounding::HalfAway) {
+        Some(std) => <T as NumCast>::from(std).unwrap_or(T::MAX),
+        None => func_fast_population_std(w),
+    }
+}
+
+/// [func_exact_population_std] rounded with `policy.rounding`, a result larger than `T::MAX` is
+/// saturated or an error depending on `policy.overflow`
+///
+/// float windows, and [i128] windows spread too wide for [window_exact_std], convert the [f64]
+/// population standard deviation with `policy`
+#[inline]
+pub fn window_exact_std_converted<T, D>(w: ArrayView<T, D>, policy: ConversionPolicy) -> Result<T, ConversionError>
+where
+    T: WinNum,
+    D: Dimension,
+{
+    match window_exact_std(w.view(), policy.rounding) {
+        Some(std) => match (<T as NumCast>::from(std), policy.overflow) {
+            (Some(x), _) => Ok(x),
+            (None, Overflow::Saturate) => Ok(T::MAX),
+            (None, Overflow::Error) => Err(ConversionError(std as f64)),
+        },
+        None => policy.convert(window_mean_population_std(w).1),
+    }
+}
+
+/// population standard deviation of an integer window with integer arithmetic only,
+/// `floor(sqrt(variance))` is an exact integer square root and `rounding` is applied to the
+/// exact value (there are no negative values so [Rounding::HalfAway] rounds ties up)
+///
+/// the sum of squared deviations from the truncated mean is kept as a multiple of the window
+/// length plus a remainder, so 128 bit accumulators are enough for 64 bit windows
+///
+/// `None` for float windows or [i128] windows with a spread of `2^64` or more
+///
+/// ```
+/// use ndarray::arr1;
+/// use ndarray_threaded_window::integer_conversion_traits::Rounding;
+/// let w = arr1(&[0u64, u64::MAX]);
+/// assert_eq!(window_exact_std(w.view(), Rounding::Floor), Some(u64::MAX as u128 / 2));
+/// assert_eq!(window_exact_std(w.view(), Rounding::HalfAway), Some(1 << 63));
+/// ```
+pub fn window_exact_std<T, D>(w: ArrayView<T, D>, rounding: Rounding) -> Option<u128>
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let n = w.len() as i128;
+    let total = w.iter().try_fold(0i128, |a, x| a.checked_add(x.as_i128()?))?;
+    let (q, r) = (total.div_euclid(n), total.rem_euclid(n));
+    // sum of (x - q)^2 as a1 * n + a0, a1 is at most the largest square so it can't overflow
+    let (mut a1, mut a0) = (0u128, 0u128);
+    for x in w.iter() {
+        let d = x.as_i128()?.checked_sub(q)?.unsigned_abs();
+        let square = d.checked_mul(d)?;
+        a1 += square / n as u128;
+        a0 += square % n as u128;
+        if a0 >= n as u128 {
+            a0 -= n as u128;
+            a1 += 1;
+        }
+    }
+    // variance = a1 + e / n^2 with -n^2 < e < n^2 (the mean is q + r / n)
+    let n2 = n * n;
+    let e = n * a0 as i128 - r * r;
+    let k = (a1 - (e < 0) as u128).isqrt();
+    let above_k = (a1 - k * k) as i128;
+    let std = match rounding {
+        Rounding::Floor | Rounding::Truncate => k,
+        Rounding::Ceil => k + (sign_of_scaled(above_k, n2, e) == Ordering::Greater) as u128,
+        // variance compared with (k + 1/2)^2, scaled by 4 n^2
+        Rounding::HalfAway | Rounding::HalfEven => match sign_of_scaled(above_k - k as i128, 4 * n2, 4 * e - n2) {
+            Ordering::Greater => k + 1,
+            Ordering::Less => k,
+            Ordering::Equal if rounding == Rounding::HalfAway => k + 1,
+            Ordering::Equal => k + k % 2,
+        },
+    };
+    Some(std)
+}
+
+/// sign of `m * scale + rest` when `|rest| < 2 * scale`, without overflowing for large `m`
+#[inline]
+fn sign_of_scaled(m: i128, scale: i128, rest: i128) -> Ordering {
+    match m.abs() > 2 {
+        true => m.cmp(&0),
+        false => (m * scale + rest).cmp(&0),
+    }
+}
+
+/// mean and population standard deviation of the window as [f64],
+/// the calculation behind [func_fast_population_std]
+///
+/// uses unsigned integer addition for the mean
+#[inline]
+pub fn window_mean_population_std<T, D>(w: ArrayView<T, D>) -> (f64, f64)
+where
+    T: WinNum,
     D: Dimension,
 {
     let len_inv = (w.len() as f64).recip();
-    let mean: f64 = T::larger_int_as_f64(
-        w.iter()
-            .fold(T::L_ZERO, |a: T::LargerInt, x: &T| a + x.as_larger_int()),
-    ) * len_inv;
+    let mean: f64 = sum_as_f64(w.iter()) * len_inv;
     let flt: f64 = w
         .iter()
         .fold(0f64, |a: f64, x: &T| a + (x.as_f64() - mean).powi(2));
 
-    T::from_f64((flt * len_inv).sqrt())
+    (mean, (flt * len_inv).sqrt())
+}
+
+/// sum of `values` as [f64], added exactly in [WinNum::Acc]
+///
+/// when the number of values makes an overflow possible (see [WinNum::acc_may_overflow]) the
+/// additions are checked and the values are summed again in [f64] if one overflows,
+/// use [thread_over_any_window_checked](crate::array_threading::thread_over_any_window_checked) to get an
+/// error instead
+#[inline]
+pub fn sum_as_f64<'a, T, I>(values: I) -> f64
+where
+    T: WinNum + 'a,
+    I: ExactSizeIterator<Item = &'a T> + Clone,
+{
+    if !T::acc_may_overflow(values.len()) {
+        return T::acc_as_f64(values.fold(T::ACC_ZERO, |a, x| a + x.as_acc()));
+    }
+    match values.clone().try_fold(T::ACC_ZERO, T::acc_checked_add) {
+        Some(total) => T::acc_as_f64(total),
+        None => values.fold(0f64, |a, x| a + x.as_f64()),
+    }
+}
+
+/// mean of the window, summed with [WinNum::Acc] so the sum is exact for integer types
+#[inline]
+fn window_mean<T, D>(w: &ArrayView<T, D>) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    sum_as_f64(w.iter()) / w.len() as f64
+}
+
+/// `k`-th central moment `sum((x - mean)^k) / n` of the window as [f64], `k = 2` is the
+/// population variance
+///
+/// integer windows are exact until the final division, the power sums `sum(x^j)` are added in
+/// [i128] and combined into `n^k` times the moment, when an [i128] overflows the deviations from
+/// the [f64] mean are summed instead
+#[inline]
+pub fn window_central_moment<T, D>(w: ArrayView<T, D>, k: u32) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let exact = window_power_sums(&w, k).and_then(|sums| central_moment_from_power_sums(&sums, k));
+    if let Some(scaled) = exact {
+        return scaled as f64 / (w.len() as f64).powi(k as i32);
+    }
+    let mean = window_mean(&w);
+    w.iter().fold(0f64, |a: f64, x: &T| a + (x.as_f64() - mean).powi(k as i32)) / w.len() as f64
+}
+
+/// power sums `sum(x^j)` for `j` in `0..=k` of an integer window in [i128],
+/// `None` for float windows or when a power or a sum overflows
+#[inline]
+fn window_power_sums<T, D>(w: &ArrayView<T, D>, k: u32) -> Option<Vec<i128>>
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let mut sums = vec![0i128; k as usize + 1];
+    sums[0] = w.len() as i128;
+    for x in w.iter() {
+        let x = x.as_i128()?;
+        let mut power = 1i128;
+        for sum in sums[1..].iter_mut() {
+            power = power.checked_mul(x)?;
+            *sum = sum.checked_add(power)?;
+        }
+    }
+    Some(sums)
+}
+
+/// `n^k` times the `k`-th central moment from the power sums of [window_power_sums],
+/// `sum(C(k, j) * (-s)^(k - j) * n^(j - 1) * p_j)` with `s = p_1` and `n^-1 * p_0 = 1`,
+/// `None` when an [i128] overflows
+#[inline]
+fn central_moment_from_power_sums(sums: &[i128], k: u32) -> Option<i128> {
+    let (n, s) = (sums[0], sums.get(1).copied().unwrap_or(0));
+    let mut binomial = 1i128;
+    let mut total = 0i128;
+    for j in 0..=k {
+        let scaled_sum = match j {
+            0 => 1i128,
+            _ => n.checked_pow(j - 1)?.checked_mul(sums[j as usize])?,
+        };
+        let term = scaled_sum.checked_mul(binomial)?.checked_mul(s.checked_neg()?.checked_pow(k - j)?)?;
+        total = total.checked_add(term)?;
+        binomial = binomial * (k - j) as i128 / (j + 1) as i128;
+    }
+    Some(total)
+}
+
+/// second, third and fourth central moments, exact for integer windows in the same way as
+/// [window_central_moment] with a single pass for the power sums
+#[inline]
+fn window_central_moments_234<T, D>(w: ArrayView<T, D>) -> (f64, f64, f64)
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let n = w.len() as f64;
+    let exact = window_power_sums(&w, 4).and_then(|sums| {
+        Some((
+            central_moment_from_power_sums(&sums[..3], 2)?,
+            central_moment_from_power_sums(&sums[..4], 3)?,
+            central_moment_from_power_sums(&sums, 4)?,
+        ))
+    });
+    if let Some((m2, m3, m4)) = exact {
+        return (m2 as f64 / (n * n), m3 as f64 / (n * n * n), m4 as f64 / (n * n * n * n));
+    }
+    let mean = window_mean(&w);
+    let len_inv = n.recip();
+    let (m2, m3, m4) = w.iter().fold((0f64, 0f64, 0f64), |(m2, m3, m4), x: &T| {
+        let d = x.as_f64() - mean;
+        let d2 = d * d;
+        (m2 + d2, m3 + d2 * d, m4 + d2 * d2)
+    });
+    (m2 * len_inv, m3 * len_inv, m4 * len_inv)
+}
+
+/// skewness `m3 / m2^1.5` of the window (the biased estimator, `scipy.stats.skew(bias=True)`),
+/// `0` when every element is the same
+#[inline]
+pub fn window_skewness<T, D>(w: ArrayView<T, D>) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let (m2, m3, _) = window_central_moments_234(w);
+    match m2 == 0f64 {
+        true => 0f64,
+        false => m3 / m2.powf(1.5),
+    }
+}
+
+/// excess kurtosis `m4 / m2^2 - 3` of the window (the biased estimator,
+/// `scipy.stats.kurtosis(bias=True)`), `0` when every element is the same
+#[inline]
+pub fn window_excess_kurtosis<T, D>(w: ArrayView<T, D>) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let (m2, _, m4) = window_central_moments_234(w);
+    match m2 == 0f64 {
+        true => 0f64,
+        false => m4 / (m2 * m2) - 3f64,
+    }
+}
+
+/// scale that makes the median absolute deviation a consistent estimator of the standard
+/// deviation of normally distributed data, `1 / Φ⁻¹(3/4)`
+pub const MAD_NORMAL_SCALE: f64 = 1.482602218505602;
+
+/// the window values in ascending order
+///
+/// types with at most 256 values (the 8 bit types) use a counting sort for windows of 64 or more
+/// elements, everything else uses [slice::sort_unstable_by] with [WinNum::total_order]
+#[inline]
+fn window_sorted<T, D>(w: &ArrayView<T, D>) -> Vec<T>
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let min = T::MIN.as_f64();
+    match T::INTEGER && T::MAX.as_f64() - min < 256f64 && w.len() >= 64 {
+        true => {
+            let span = (T::MAX.as_f64() - min) as usize + 1;
+            let mut counts = [0usize; 256];
+            for x in w.iter() {
+                counts[(x.as_f64() - min) as usize] += 1;
+            }
+            let mut sorted = Vec::with_capacity(w.len());
+            for (i, &c) in counts.iter().enumerate().take(span) {
+                sorted.extend(std::iter::repeat_n(T::from_f64(i as f64 + min), c));
+            }
+            sorted
+        }
+        false => {
+            let mut sorted: Vec<T> = w.iter().copied().collect();
+            sorted.sort_unstable_by(T::total_order);
+            sorted
+        }
+    }
+}
+
+/// median of an ascending slice, the mean of the two middle values for an even length
+#[inline]
+fn sorted_median<T: WinNum>(sorted: &[T]) -> f64 {
+    let n = sorted.len();
+    match n % 2 {
+        1 => sorted[n / 2].as_f64(),
+        _ => (sorted[n / 2 - 1].as_f64() + sorted[n / 2].as_f64()) * 0.5,
+    }
+}
+
+/// mean of a slice, summed with [WinNum::Acc] so the sum is exact for integer types
+#[inline]
+fn slice_mean<T: WinNum>(values: &[T]) -> f64 {
+    sum_as_f64(values.iter()) / values.len() as f64
+}
+
+/// number of elements cut from each end for a proportion `alpha`,
+/// limited so at least one element is left
+#[inline]
+fn trim_count(len: usize, alpha: f64) -> usize {
+    ((alpha.max(0f64) * len as f64) as usize).min((len - 1) / 2)
+}
+
+/// median absolute deviation `median(|x - median(x)|)` of the window multiplied by `scale`,
+/// use `1.0` for the raw value or [MAD_NORMAL_SCALE] to estimate the standard deviation
+#[inline]
+pub fn window_mad<T, D>(w: ArrayView<T, D>, scale: f64) -> f64
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let sorted = window_sorted(&w);
+    let median = sorted_median(&sorted);
+    let mut deviations: Vec<f64> = sorted.iter().map(|x| (x.as_f64() - median).abs()).collect();
+    let n = deviations.len();
+    let (below, &mut upper, _) = deviations.select_nth_unstable_by(n / 2, f64::total_cmp);
+    let mad = match n % 2 {
+        1 => upper,
+        _ => (below.iter().fold(f64::MIN, |a, &x| a.max(x)) + upper) * 0.5,
+    };
+    mad * scale
+}
+
+/// alpha-trimmed mean, the mean after dropping the `alpha * n` (rounded down) smallest and largest
+/// values of the window, `alpha` of `0.5` or more leaves the median
+#[inline]
+pub fn window_trimmed_mean<T, D>(w: ArrayView<T, D>, alpha: f64) -> f64
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let sorted = window_sorted(&w);
+    let g = trim_count(sorted.len(), alpha);
+    slice_mean(&sorted[g..sorted.len() - g])
+}
+
+/// winsorised mean, the `alpha * n` (rounded down) smallest and largest values of the window are
+/// replaced by the nearest value which is kept before taking the mean
+#[inline]
+pub fn window_winsorised_mean<T, D>(w: ArrayView<T, D>, alpha: f64) -> f64
+where
+    T: WinNum + Copy,
+    D: Dimension,
+{
+    let mut sorted = window_sorted(&w);
+    let n = sorted.len();
+    let g = trim_count(n, alpha);
+    let (lo, hi) = (sorted[g], sorted[n - 1 - g]);
+    sorted[..g].fill(lo);
+    sorted[n - g..].fill(hi);
+    slice_mean(&sorted)
+}
+
+/// means of two windows and the population covariance between them
+#[inline]
+fn window_pair_moments<T, D>(a: &ArrayView<T, D>, b: &ArrayView<T, D>) -> (f64, f64, f64, f64, f64)
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let (mean_a, mean_b) = (window_mean(a), window_mean(b));
+    let (var_a, var_b, cov) = a.iter().zip(b.iter()).fold((0f64, 0f64, 0f64), |(va, vb, c), (x, y)| {
+        let (dx, dy) = (x.as_f64() - mean_a, y.as_f64() - mean_b);
+        (va + dx * dx, vb + dy * dy, c + dx * dy)
+    });
+    let len_inv = (a.len() as f64).recip();
+    (mean_a, mean_b, var_a * len_inv, var_b * len_inv, cov * len_inv)
+}
+
+/// population covariance of two windows of the same shape
+#[inline]
+pub fn window_covariance<T, D>([a, b]: [ArrayView<T, D>; 2]) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    window_pair_moments(&a, &b).4
+}
+
+/// Pearson correlation coefficient of two windows of the same shape,
+/// `0` when either window is flat
+#[inline]
+pub fn window_pearson_correlation<T, D>([a, b]: [ArrayView<T, D>; 2]) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let (_, _, var_a, var_b, cov) = window_pair_moments(&a, &b);
+    match var_a == 0f64 || var_b == 0f64 {
+        true => 0f64,
+        false => cov / (var_a * var_b).sqrt(),
+    }
+}
+
+/// mean of the `values` window weighted by the `weights` window, `0` when the weights sum to `0`
+///
+/// both sums of the weights use [WinNum::Acc]
+#[inline]
+pub fn window_weighted_mean<T, D>([values, weights]: [ArrayView<T, D>; 2]) -> f64
+where
+    T: WinNum,
+    D: Dimension,
+{
+    let total = sum_as_f64(weights.iter());
+    match total == 0f64 {
+        true => 0f64,
+        false => {
+            values
+                .iter()
+                .zip(weights.iter())
+                .fold(0f64, |a, (v, w)| a + v.as_f64() * w.as_f64())
+                / total
+        }
+    }
+}
+
+/// how NaN elements of a float window are treated by [window_nan_reduce]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NanPolicy {
+    /// any NaN in the window makes the result NaN
+    Propagate,
+    /// NaN elements are skipped, like `numpy.nanmax` and friends, the result is NaN when
+    /// fewer than `min_count` (at least one) valid elements are left
+    Omit { min_count: usize },
+}
+
+/// reduction of a float window calculated by [window_nan_reduce]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NanReduction {
+    Max,
+    Min,
+    Mean,
+    /// standard deviation with `ddof` delta degrees of freedom, NaN when there are
+    /// `ddof` or fewer valid elements
+    Std { ddof: usize },
+    /// the mean of the two middle values for an even count
+    Median,
+}
+
+/// `reduction` of the window with NaN handled by `policy`, summed in [f64]
+///
+/// ```
+/// use ndarray::arr1;
+/// let a = arr1(&[1f32, f32::NAN, 3.0]);
+/// let omit = NanPolicy::Omit { min_count: 2 };
+/// assert_eq!(window_nan_reduce(a.view(), NanReduction::Mean, omit), 2.0);
+/// assert!(window_nan_reduce(a.view(), NanReduction::Mean, NanPolicy::Propagate).is_nan());
+/// ```
+#[inline]
+pub fn window_nan_reduce<T, D>(w: ArrayView<T, D>, reduction: NanReduction, policy: NanPolicy) -> f64
+where
+    T: FloatConv,
+    D: Dimension,
+{
+    let mut valid: Vec<f64> = w.iter().map(|x| x.as_f64()).filter(|x| !x.is_nan()).collect();
+    let n = valid.len();
+    let enough = match policy {
+        NanPolicy::Propagate => n == w.len() && n > 0,
+        NanPolicy::Omit { min_count } => n >= min_count.max(1),
+    };
+    if !enough {
+        return f64::NAN;
+    }
+    match reduction {
+        NanReduction::Max => valid.iter().fold(f64::NEG_INFINITY, |a, &x| a.max(x)),
+        NanReduction::Min => valid.iter().fold(f64::INFINITY, |a, &x| a.min(x)),
+        NanReduction::Mean => valid.iter().sum::<f64>() / n as f64,
+        NanReduction::Std { ddof } => match n > ddof {
+            true => {
+                let mean = valid.iter().sum::<f64>() / n as f64;
+                let sq = valid.iter().fold(0f64, |a, &x| a + (x - mean).powi(2));
+                (sq / (n - ddof) as f64).sqrt()
+            }
+            false => f64::NAN,
+        },
+        NanReduction::Median => {
+            let (below, &mut upper, _) = valid.select_nth_unstable_by(n / 2, f64::total_cmp);
+            match n % 2 {
+                1 => upper,
+                _ => (below.iter().fold(f64::NEG_INFINITY, |a, &x| a.max(x)) + upper) * 0.5,
+            }
+        }
+    }
 }
 
 /// almost equivalent to ```ndarray::array.std(ddof=1))```
@@ -228,23 +944,29 @@ where
 /// let nd_std = array.std(1);
 /// assert_eq![pop_std, nd_std] // this is not always correct 2023-01-13
 /// ```
-/// uses unsigned integer addition
-/// overflows are possible on u64 arrays, or u32 arrays with more than `4*10^6` items
+/// uses integer addition for the mean, see [sum_as_f64]
 #[inline]
 pub fn func_fast_sample_std<T, D>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv,
+    T: WinNum,
+    D: Dimension,
+{
+    T::from_f64(window_fast_sample_std(w))
+}
+
+/// the calculation behind [func_fast_sample_std] as [f64]
+#[inline]
+pub fn window_fast_sample_std<T, D>(w: ArrayView<T, D>) -> f64
+where
+    T: WinNum,
     D: Dimension,
 {
     let len_inv = (w.len() as f64).recip();
-    let mean: f64 = T::larger_int_as_f64(
-        w.iter()
-            .fold(T::L_ZERO, |a: T::LargerInt, x: &T| a + x.as_larger_int()),
-    ) * len_inv;
+    let mean: f64 = sum_as_f64(w.iter()) * len_inv;
     let flt: f64 = w
         .iter()
-        .fold(0f64, |a: f64, x: &T| a + (x.as_f64().abs() - mean).powi(2));
-    T::from_f64((flt * ((w.len() - 1) as f64).recip()).sqrt())
+        .fold(0f64, |a: f64, x: &T| a + (x.as_f64() - mean).powi(2));
+    (flt * ((w.len() - 1) as f64).recip()).sqrt()
 }
 
 /// standard deviation calculated with [func_fast_std] then doubled
@@ -252,20 +974,24 @@ where
 #[inline]
 pub fn func_fast_std_clamp<T, D>(w: ArrayView<T, D>) -> T
 where
-    T: IntConv,
+    T: WinNum,
     D: Dimension,
 {
     let len_inv = (w.len() as f64).recip();
-    let mean: f64 = T::larger_int_as_f64(
-        w.iter()
-            .fold(T::L_ZERO, |a: T::LargerInt, x: &T| a + x.as_larger_int()),
-    ) * len_inv;
+    let mean: f64 = sum_as_f64(w.iter()) * len_inv;
     let flt: f64 = w
         .iter()
         .fold(0f64, |a: f64, x: &T| a + (x.as_f64() - mean).abs().powi(2));
     T::from_f64((flt * len_inv).sqrt() * 2f64)
 }
 
+/// deterministic test data, `476579 % (product of the index + 1)` truncated to [u8]
+#[cfg(test)]
+pub(crate) fn not_a_hash<I: ndarray::IntoDimension>(ix: I) -> u8 {
+    let ix = ix.into_dimension();
+    (476579u64 % (ix.slice().iter().product::<usize>() + 1) as u64) as u8
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::hash_map::DefaultHasher;
@@ -273,21 +999,21 @@ mod tests {
     use std::ops::Div;
     use std::time;
 
-    use ndarray::{Array1, Array2, Array3, Axis, Ix3};
+    use ndarray::{Array1, Array2, Array3, Axis, Ix1, Ix3};
 
     use crate::window_functions::{
-        func_area_contrast, func_fast_std, func_fast_std_clamp, func_stdev_ddof_0,
-        func_stdev_ddof_1, func_window_max, func_window_min, WinFunc,
+        func_area_contrast, func_fast_population_std, func_fast_std_clamp, func_stdev_ddof_0,
+        func_michelson_contrast, func_stdev_ddof_1, func_window_max, func_window_min,
+        window_central_moment, window_excess_kurtosis, window_mad, window_michelson_contrast,
+        sum_as_f64, window_nan_reduce, window_skewness, window_trimmed_mean, window_weber_contrast,
+        window_winsorised_mean, NanPolicy, NanReduction, WinFunc, MAD_NORMAL_SCALE,
+    };
+    use crate::integer_conversion_traits::{ConversionError, ConversionPolicy, Overflow, Rounding};
+    use crate::window_functions::{
+        func_exact_population_std, get_func, numpy_pairwise_sum, window_exact_std, window_fast_sample_std, window_std,
     };
 
-    use super::func_fast_std_pure_int;
-
-    fn not_a_hash((a, b, c): (usize, usize, usize)) -> u8 {
-        ((476579u64 % (a * b * c + 1) as u64) % 256) as u8
-    }
-    fn not_a_hash2((a, b): (usize, usize)) -> u16 {
-        ((476579u64 % (a * b + 1) as u64) % 256) as u16
-    }
+    use super::{func_fast_std_pure_int, not_a_hash, window_central_moments_234};
 
     fn generate_array3() -> Array3<u8> {
         Array3::from_shape_fn((8, 8, 1), not_a_hash)
@@ -308,7 +1034,7 @@ mod tests {
         let iterations = 100000;
 
         work(iterations, func_area_contrast, "area_contrast");
-        work(iterations, func_fast_std, "fast_std");
+        work(iterations, func_fast_population_std, "fast_population_std");
         work(iterations, func_fast_std_clamp, "fast_std_clamp");
         work(iterations, func_stdev_ddof_0, "stdev_ddof_0");
         work(iterations, func_stdev_ddof_1, "stdev_ddof_1");
@@ -342,17 +1068,262 @@ mod tests {
     #[test]
     fn test_faster_rms_u64_adding() {
         let a = generate_array3();
-        let b = func_fast_std(a.view());
+        let b = func_fast_population_std(a.view());
 
         let mut hasher = DefaultHasher::new();
         b.hash(&mut hasher);
         assert_eq!(7541581120933061747, hasher.finish());
     }
 
+    #[test]
+    fn contrast_reference_formulas() {
+        let a = Array1::from(vec![10u8, 40, 20, 30]);
+        // mean 25, squared deviations 225 + 225 + 25 + 25
+        assert_eq!(func_area_contrast(a.view()), 11);
+        assert_eq!(func_michelson_contrast(a.view()), 153);
+        assert_eq!(window_michelson_contrast(a.view()), 0.6);
+        // signed values start at i8::MIN, -5 and 6 are 123 and 134
+        let signed = Array1::from(vec![-5i8, 6, 0]);
+        assert_eq!(window_michelson_contrast(signed.view()), 11.0 / 257.0);
+        assert_eq!(window_michelson_contrast(Array1::from(vec![i8::MIN, i8::MAX]).view()), 1.0);
+        assert_eq!(func_michelson_contrast(Array1::from(vec![i16::MIN, i16::MAX]).view()), i16::MAX);
+        // floats are not offset
+        assert_eq!(window_michelson_contrast(Array1::from(vec![-1.0f64, 0.5]).view()), -3.0);
+        // centre is element 2, background (10 + 40 + 30) / 3
+        assert!((window_weber_contrast(a.view()) - (20.0 - 80.0 / 3.0) / (80.0 / 3.0)).abs() < 1e-12);
+        // no background, an empty window has no centre either
+        assert_eq!(window_weber_contrast(a.slice(ndarray::s![..1])), 0.0);
+        assert_eq!(window_weber_contrast(a.slice(ndarray::s![..0])), 0.0);
+    }
+
+    #[test]
+    fn moments_reference_formulas() {
+        let a = Array1::from(vec![1u8, 2, 2, 3, 9]);
+        let x = a.mapv(|x| x as f64);
+        let mean = x.sum() / 5.0;
+        let m = |k: i32| x.mapv(|x| (x - mean).powi(k)).sum() / 5.0;
+        assert!((window_central_moment(a.view(), 2) - x.var(0.0)).abs() < 1e-12);
+        assert!((window_central_moment(a.view(), 5) - m(5)).abs() < 1e-9);
+        assert!((window_skewness(a.view()) - m(3) / m(2).powf(1.5)).abs() < 1e-12);
+        assert!((window_excess_kurtosis(a.view()) - (m(4) / m(2).powi(2) - 3.0)).abs() < 1e-12);
+
+        let symmetric = Array1::from(vec![-4i16, -1, 0, 1, 4]);
+        assert_eq!(window_skewness(symmetric.view()), 0.0);
+        let flat = Array1::from(vec![7u32; 6]);
+        assert_eq!((window_skewness(flat.view()), window_excess_kurtosis(flat.view())), (0.0, 0.0));
+    }
+
+    #[test]
+    fn integer_moments_are_exact() {
+        // sum((x - mean)^k) / n == sum((n x - s)^k) / n^(k + 1), all in integers
+        let a = Array1::from(vec![3i16, -7, 12, 0, 5, 5, -1]);
+        let (n, s) = (7i128, a.iter().map(|&x| x as i128).sum::<i128>());
+        for k in 0..7u32 {
+            let scaled: i128 = a.iter().map(|&x| (n * x as i128 - s).pow(k)).sum();
+            let expected = scaled as f64 / (n as f64).powi(k as i32 + 1);
+            assert!((window_central_moment(a.view(), k) - expected).abs() <= expected.abs() * 1e-15, "k = {}", k);
+        }
+        // a large offset loses the small deviations in a f64 mean
+        let offset = Array1::from(vec![(1i64 << 53) + 1, (1 << 53) + 2, (1 << 53) + 6]);
+        assert_eq!(window_central_moment(offset.view(), 2), 14.0 / 3.0);
+        let offset = offset.mapv(|x| x - (1 << 53) + (1 << 28));
+        assert_eq!(window_central_moments_234(offset.view()), (14.0 / 3.0, 6.0, 98.0 / 3.0));
+        // powers beyond i128 use the f64 deviations
+        let wide = Array1::from(vec![i64::MAX, 0, i64::MAX, 0]);
+        let m4 = (i64::MAX as f64 / 2.0).powi(4);
+        assert!((window_central_moment(wide.view(), 4) - m4).abs() / m4 < 1e-12);
+        assert!((window_excess_kurtosis(wide.view()) + 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn robust_reference_formulas() {
+        let a = Array1::from(vec![1u16, 2, 3, 4, 100, 6, 7, 8, 9, 5]);
+        // sorted 1..=9 and 100, median 5.5, deviations 0.5 0.5 1.5 1.5 .. 4.5 94.5
+        assert_eq!(window_mad(a.view(), 1.0), 2.5);
+        assert_eq!(window_mad(a.view(), MAD_NORMAL_SCALE), 2.5 * MAD_NORMAL_SCALE);
+        // 10% trims one value from each end, 2..=9
+        assert_eq!(window_trimmed_mean(a.view(), 0.1), 5.5);
+        assert_eq!(window_trimmed_mean(a.view(), 0.0), 14.5);
+        // 1 becomes 2 and 100 becomes 9
+        assert_eq!(window_winsorised_mean(a.view(), 0.1), 5.5);
+        assert_eq!(window_winsorised_mean(a.view(), 0.2), 5.5);
+        assert_eq!(window_trimmed_mean(a.view(), 0.5), 5.5);
+    }
+
+    #[test]
+    fn counting_sort_matches() {
+        let a: Array3<u8> = Array3::from_shape_fn((8, 8, 3), not_a_hash);
+        let b = a.mapv(|x| x as u32);
+        for alpha in [0.0, 0.1, 0.25, 0.5] {
+            assert_eq!(window_trimmed_mean(a.view(), alpha), window_trimmed_mean(b.view(), alpha));
+            assert_eq!(window_winsorised_mean(a.view(), alpha), window_winsorised_mean(b.view(), alpha));
+        }
+        assert_eq!(window_mad(a.view(), 1.0), window_mad(b.view(), 1.0));
+        let i = a.mapv(|x| (x as i16 - 128) as i8);
+        let j = i.mapv(|x| x as i32);
+        assert_eq!(window_mad(i.view(), 1.0), window_mad(j.view(), 1.0));
+        assert_eq!(window_trimmed_mean(i.view(), 0.2), window_trimmed_mean(j.view(), 0.2));
+    }
+
+    #[test]
+    fn float_windows() {
+        let a = Array1::from(vec![-1.5f32, 4.0, 0.25, 2.0, -3.0]);
+        assert_eq!(func_window_max(a.view()), 4.0);
+        assert_eq!(func_window_min(a.view()), -3.0);
+        let x = a.mapv(|x| x as f64);
+        assert!((func_stdev_ddof_0(a.view()) as f64 - x.std(0.0)).abs() < 1e-6);
+        assert!((func_area_contrast(a.view()) as f64 - x.std(0.0)).abs() < 1e-6);
+        // sorted -3 -1.5 0.25 2 4
+        assert_eq!(window_trimmed_mean(a.view(), 0.2), 0.25);
+        assert_eq!(window_mad(a.view(), 1.0), 1.75);
+
+        let b = Array1::from(vec![0.25f64, 0.75, 0.5]);
+        assert_eq!(func_michelson_contrast(b.view()), 0.5);
+        assert_eq!(func_michelson_contrast(b.view()), window_michelson_contrast(b.view()));
+        let whole = Array1::from(vec![f64::NEG_INFINITY, 1.0]);
+        assert_eq!(func_window_min(whole.view()), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn sample_std_of_negative_values() {
+        let a = Array1::from(vec![-1i8, -1]);
+        assert_eq!(window_fast_sample_std(a.view()), 0.0);
+        let b = Array1::from(vec![-3.5f64, 2.0, -0.25, 7.0]);
+        assert!((window_fast_sample_std(b.view()) - b.std(1.0)).abs() < 1e-12);
+        let c = Array1::from(vec![-9i32, 4, -2, 0, 5]);
+        assert!((window_fast_sample_std(c.view()) - c.mapv(|x| x as f64).std(1.0)).abs() < 1e-12);
+        assert_eq!(get_func::<i32, Ix1>(8).unwrap()(c.view()), c.mapv(|x| x as f64).std(1.0).round() as i32);
+    }
+
+    #[test]
+    fn nan_policies() {
+        let a = Array1::from(vec![2f64, f64::NAN, 7.0, 1.0, f64::NAN]);
+        let omit = NanPolicy::Omit { min_count: 1 };
+        assert_eq!(window_nan_reduce(a.view(), NanReduction::Max, omit), 7.0);
+        assert_eq!(window_nan_reduce(a.view(), NanReduction::Min, omit), 1.0);
+        assert_eq!(window_nan_reduce(a.view(), NanReduction::Mean, omit), 10.0 / 3.0);
+        assert_eq!(window_nan_reduce(a.view(), NanReduction::Median, omit), 2.0);
+        let std = window_nan_reduce(a.view(), NanReduction::Std { ddof: 1 }, omit);
+        assert!((std - Array1::from(vec![2f64, 7.0, 1.0]).std(1.0)).abs() < 1e-12);
+        for reduction in [NanReduction::Max, NanReduction::Mean, NanReduction::Median] {
+            assert!(window_nan_reduce(a.view(), reduction, NanPolicy::Propagate).is_nan());
+            assert!(window_nan_reduce(a.view(), reduction, NanPolicy::Omit { min_count: 4 }).is_nan());
+        }
+        let even = Array1::from(vec![4f32, f32::NAN, 1.0, 3.0, 8.0]);
+        assert_eq!(window_nan_reduce(even.view(), NanReduction::Median, omit), 3.5);
+        let empty = Array1::from(vec![f32::NAN; 3]);
+        assert!(window_nan_reduce(empty.view(), NanReduction::Max, NanPolicy::Omit { min_count: 0 }).is_nan());
+    }
+
+    #[test]
+    fn wide_integers_do_not_overflow() {
+        let a = Array1::from(vec![u64::MAX, 0, u64::MAX, 0]);
+        let expected = u64::MAX as f64 / 2.0;
+        assert_eq!(func_fast_population_std(a.view()) as f64, expected);
+        assert_eq!(func_fast_std_pure_int(a.view()) as f64, expected);
+        assert_eq!(func_window_max(a.view()), u64::MAX);
+
+        let b = Array1::from(vec![i128::MAX, i128::MAX, i128::MIN + 1, i128::MAX]);
+        assert_eq!(func_window_min(b.view()), i128::MIN + 1);
+        let mean = (i128::MAX as f64) * 0.5;
+        assert!((sum_as_f64(b.view().iter()) / 4.0 - mean).abs() / mean < 1e-12);
+        assert!(func_fast_std_pure_int(b.view()) > 0);
+
+        let c = Array1::from(vec![3usize, 5, 7, 9]);
+        assert_eq!(func_fast_std_pure_int(c.view()), func_fast_population_std(c.view()));
+        let d = Array1::from(vec![-3isize, 5, -7, 9]);
+        assert_eq!(window_trimmed_mean(d.view(), 0.25), 1.0);
+    }
+
+    #[test]
+    fn conversion_policies() {
+        let policy = |rounding| ConversionPolicy { rounding, overflow: Overflow::Saturate };
+        let expected = [
+            (Rounding::HalfEven, [2i8, -2, 4, 2]),
+            (Rounding::HalfAway, [3, -3, 4, 2]),
+            (Rounding::Floor, [2, -3, 3, 1]),
+            (Rounding::Ceil, [3, -2, 4, 2]),
+            (Rounding::Truncate, [2, -2, 3, 1]),
+        ];
+        for (rounding, values) in expected {
+            let out = [2.5, -2.5, 3.5, 1.7].map(|x| policy(rounding).convert::<i8>(x).unwrap());
+            assert_eq!(out, values, "{:?}", rounding);
+        }
+        assert_eq!(ConversionPolicy::DEFAULT.convert::<u8>(300.0), Ok(255));
+        assert_eq!(ConversionPolicy::DEFAULT.convert::<u8>(f64::NAN), Ok(0));
+        assert_eq!(ConversionPolicy::NUMPY.convert::<u8>(255.9), Ok(255));
+        assert_eq!(ConversionPolicy::NUMPY.convert::<u8>(256.0), Err(ConversionError(256.0)));
+        assert!(ConversionPolicy::NUMPY.convert::<i32>(f64::NAN).is_err());
+        assert!(ConversionPolicy::NUMPY.convert::<u64>(u64::MAX as f64).is_err());
+        assert_eq!(ConversionPolicy::NUMPY.convert::<f32>(2.5), Ok(2.5));
+        assert!(ConversionPolicy::NUMPY.convert::<f32>(1e300).is_err());
+        assert!(ConversionPolicy::NUMPY.convert::<f64>(f64::NAN).unwrap().is_nan());
+    }
+
+    #[test]
+    fn numpy_std() {
+        // np.std([1, 2, 3, 4, 5, 6]) and np.std([1, 2, 3, 4, 5, 6], ddof=1)
+        let a = Array1::from(vec![1u8, 2, 3, 4, 5, 6]);
+        assert_eq!(window_std(a.view(), 0), 1.707825127659933);
+        assert_eq!(window_std(a.view(), 1), 1.8708286933869707);
+        // a whole std is not truncated down by rounding errors
+        let b = Array2::from_shape_fn((16, 16), |(r, c)| if (r + c) % 2 == 0 { 0u16 } else { 6 });
+        assert_eq!(ConversionPolicy::NUMPY.convert::<u16>(window_std(b.view(), 0)), Ok(3));
+        // longer sums are split in blocks of 128, 8 accumulators in each
+        let c: Vec<f64> = (0..300).map(|i| 0.1 * i as f64).collect();
+        let block = |v: &[f64]| {
+            let (unrolled, tail) = v.split_at(v.len() - v.len() % 8);
+            let mut r = [0f64; 8];
+            for (i, x) in unrolled.iter().enumerate() {
+                r[i % 8] += x;
+            }
+            let res = ((r[0] + r[1]) + (r[2] + r[3])) + ((r[4] + r[5]) + (r[6] + r[7]));
+            tail.iter().fold(res, |a, x| a + x)
+        };
+        let halves = (block(&c[..72]) + block(&c[72..144])) + (block(&c[144..216]) + block(&c[216..]));
+        assert_eq!(numpy_pairwise_sum(&c), halves);
+        assert_eq!(numpy_pairwise_sum(&c[..5]), (((0.0 + 0.1) + 0.2) + c[3]) + c[4]);
+    }
+
+    #[test]
+    fn exact_std() {
+        // n^2 * variance = n * sum(x^2) - sum(x)^2, so std = sqrt(s) / n
+        let a = Array2::from_shape_fn((40, 9), |(r, c)| not_a_hash((r, c, 7)) as i16 - 100);
+        for w in a.windows((3, 3)).into_iter().chain(a.windows((1, 9))).chain(a.windows((40, 1))) {
+            let n = w.len() as u128;
+            let total: i128 = w.iter().map(|&x| x as i128).sum();
+            let squares: i128 = w.iter().map(|&x| (x as i128).pow(2)).sum();
+            let s = (n as i128 * squares - total * total) as u128;
+            assert_eq!(window_exact_std(w, Rounding::Floor), Some(s.isqrt() / n));
+            assert_eq!(window_exact_std(w, Rounding::HalfAway), Some(((4 * s).isqrt() + n) / (2 * n)));
+            let t = s.isqrt();
+            let ceil = if t * t == s { t.div_ceil(n) } else { t / n + 1 };
+            assert_eq!(window_exact_std(w, Rounding::Ceil), Some(ceil));
+            assert_eq!(func_exact_population_std(w) as u128, ((4 * s).isqrt() + n) / (2 * n));
+        }
+
+        let ties = [(vec![0u8, 1], [0, 1, 1, 0]), (vec![0, 3], [1, 2, 2, 2]), (vec![4, 4, 4], [0, 0, 0, 0])];
+        let modes = [Rounding::Floor, Rounding::HalfAway, Rounding::Ceil, Rounding::HalfEven];
+        for (values, expected) in ties {
+            let w = Array1::from(values);
+            assert_eq!(modes.map(|m| window_exact_std(w.view(), m).unwrap()), expected.map(|x| x as u128));
+        }
+
+        let wide = Array1::from(vec![i64::MIN, i64::MAX, i64::MIN, i64::MAX]);
+        assert_eq!(window_exact_std(wide.view(), Rounding::Floor), Some(u64::MAX as u128 / 2));
+        assert_eq!(func_exact_population_std(wide.view()), i64::MAX);
+        let third = Array1::from(vec![u64::MAX, 0, 0]);
+        // sqrt(2) * (2^64 - 1) / 3
+        assert_eq!(window_exact_std(third.view(), Rounding::Floor), Some(8695878550221854807));
+        assert_eq!(func_exact_population_std(Array1::from(vec![1.0f32, 3.0]).view()), 1.0);
+        let too_wide = Array1::from(vec![i128::MIN, i128::MAX]);
+        assert_eq!(window_exact_std(too_wide.view(), Rounding::Floor), None);
+    }
+
     #[test]
     fn comp_pure_int() {
         let arl = 1000000usize;
-        let ar2: Array2<u16> = Array2::from_shape_fn((arl, 8), not_a_hash2);
+        let ar2: Array2<u16> = Array2::from_shape_fn((arl, 8), |ix| not_a_hash(ix) as u16);
         let mut ar1: Array1<f64> = Array1::zeros(arl);
         for (o, ar) in ar1.iter_mut().zip(ar2.axis_iter(Axis(0))) {
             let trusted = ar.mapv(|elem| elem as f64).std(0f64);